@@ -0,0 +1,18 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct TexturePortSettings {
+    port: u32,
+#ifdef SIXTEEN_BYTE_ALIGNMENT
+    // WebGL2 structs must be 16 byte aligned.
+    _webgl2_padding: vec3<f32>
+#endif
+}
+
+@group(0) @binding(0) var<uniform> settings: TexturePortSettings;
+@group(0) @binding(1) var in_texture: texture_2d<f32>;
+@group(0) @binding(2) var texture_sampler: sampler;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(in_texture, texture_sampler, in.uv);
+}
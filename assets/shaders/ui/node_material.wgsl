@@ -4,6 +4,8 @@ struct NodeMaterial {
     selected: u32,
     category_color: vec4<f32>,
     disabled: u32,
+    heat: f32,
+    error: u32,
 }
 
 @group(2) @binding(0) var<uniform> material: NodeMaterial;
@@ -28,14 +30,22 @@ fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
             }
         }
 
-        return vec4<f32>(0.1, 0.1, 0.1, 1.0);
+        let hot = mix(vec4<f32>(1.0, 1.0, 0.0, 1.0), vec4<f32>(1.0, 0.0, 0.0, 1.0), material.heat);
+        return mix(vec4<f32>(0.1, 0.1, 0.1, 1.0), hot, material.heat);
     } else if (mesh.uv.x > 0.9 && mesh.uv.y > 0.9) {
         if (material.disabled == 1) {
             return vec4<f32>(1.0, 0.0, 0.0, 1.0);
         }
     }
 
-    return textureSample(image_texture, image_sampler, map_uv(mesh.uv));
+    let color = textureSample(image_texture, image_sampler, map_uv(mesh.uv));
+
+    // A banner across the top of nodes whose script has errors
+    if (material.error == 1 && mesh.uv.y < 0.2) {
+        return mix(color, vec4<f32>(0.8, 0.0, 0.0, 1.0), 0.8);
+    }
+
+    return color;
 }
 
 fn map_uv(uv: vec2<f32>) -> vec2<f32> {
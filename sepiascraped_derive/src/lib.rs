@@ -0,0 +1,257 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Expr, Fields, LitStr, Meta};
+
+/// Derive `OpParams` for a struct whose fields annotated with `#[param]` are the params of an
+/// op, in the order they're declared in.
+///
+/// ```ignore
+/// #[derive(Default, OpParams)]
+/// pub struct TextureRampSettings {
+///     #[param(color, default = Vec4::new(1.0, 0.0, 0.0, 1.0))]
+///     pub color_a: Vec4,
+///     #[param(name = "Mode", choices = TextureRampMode::ALL.map(|m| (m.as_str(), m.as_u32())))]
+///     pub mode: u32,
+/// }
+/// ```
+///
+/// The param is named after the field in title case, i.e. `color_a` is `Color A`, unless a
+/// `name` is given. Its default is the field's value in `Default::default()`, unless a `default`
+/// is given. `color` makes a `Vec4` field a color param. The rest of the arguments set the
+/// param's `ParamMeta`: `min`, `max`, `soft_min`, `soft_max`, `step`, `unit`, `tooltip`,
+/// `section` and `choices`, as well as its `page`.
+#[proc_macro_derive(OpParams, attributes(param))]
+pub fn derive_op_params(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match op_params(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+#[derive(Default)]
+struct ParamAttr {
+    name: Option<LitStr>,
+    default: Option<Expr>,
+    color: bool,
+    min: Option<Expr>,
+    max: Option<Expr>,
+    soft_min: Option<Expr>,
+    soft_max: Option<Expr>,
+    step: Option<Expr>,
+    unit: Option<LitStr>,
+    tooltip: Option<LitStr>,
+    page: Option<LitStr>,
+    section: Option<LitStr>,
+    choices: Option<Expr>,
+}
+
+impl ParamAttr {
+    fn parse(attr: &Attribute) -> syn::Result<Self> {
+        let mut param = ParamAttr::default();
+        // A bare `#[param]` uses the defaults
+        if let Meta::Path(_) = attr.meta {
+            return Ok(param);
+        }
+
+        attr.parse_nested_meta(|meta| {
+            let path = &meta.path;
+            if path.is_ident("color") {
+                param.color = true;
+            } else if path.is_ident("name") {
+                param.name = Some(meta.value()?.parse()?);
+            } else if path.is_ident("default") {
+                param.default = Some(meta.value()?.parse()?);
+            } else if path.is_ident("min") {
+                param.min = Some(meta.value()?.parse()?);
+            } else if path.is_ident("max") {
+                param.max = Some(meta.value()?.parse()?);
+            } else if path.is_ident("soft_min") {
+                param.soft_min = Some(meta.value()?.parse()?);
+            } else if path.is_ident("soft_max") {
+                param.soft_max = Some(meta.value()?.parse()?);
+            } else if path.is_ident("step") {
+                param.step = Some(meta.value()?.parse()?);
+            } else if path.is_ident("unit") {
+                param.unit = Some(meta.value()?.parse()?);
+            } else if path.is_ident("tooltip") {
+                param.tooltip = Some(meta.value()?.parse()?);
+            } else if path.is_ident("page") {
+                param.page = Some(meta.value()?.parse()?);
+            } else if path.is_ident("section") {
+                param.section = Some(meta.value()?.parse()?);
+            } else if path.is_ident("choices") {
+                param.choices = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("unknown param argument"));
+            }
+            Ok(())
+        })?;
+
+        Ok(param)
+    }
+
+    /// Statements that set up a `meta` binding of the param's `ParamMeta`.
+    fn meta(&self) -> TokenStream2 {
+        let mut setters = vec![];
+        let f32_fields = [
+            (quote!(min), &self.min),
+            (quote!(max), &self.max),
+            (quote!(soft_min), &self.soft_min),
+            (quote!(soft_max), &self.soft_max),
+        ];
+        for (field, value) in f32_fields {
+            if let Some(value) = value {
+                setters.push(quote!(meta.#field = Some((#value) as f32);));
+            }
+        }
+        if let Some(step) = &self.step {
+            setters.push(quote!(meta.step = Some((#step) as f64);));
+        }
+        let string_fields = [
+            (quote!(unit), &self.unit),
+            (quote!(tooltip), &self.tooltip),
+            (quote!(section), &self.section),
+        ];
+        for (field, value) in string_fields {
+            if let Some(value) = value {
+                setters.push(quote!(meta.#field = Some(#value.to_string());));
+            }
+        }
+        if let Some(choices) = &self.choices {
+            setters.push(quote!(meta = meta.choices(#choices);));
+        }
+
+        quote! {
+            #[allow(unused_mut)]
+            let mut meta = crate::engine::param::ParamMeta::default();
+            #(#setters)*
+        }
+    }
+}
+
+fn op_params(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            ident,
+            "OpParams can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            ident,
+            "OpParams needs a struct with named fields",
+        ));
+    };
+
+    let mut bundles = vec![];
+    let mut names = vec![];
+    let mut idents = vec![];
+    let mut types = vec![];
+    for field in &fields.named {
+        let Some(attr) = field
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("param"))
+        else {
+            continue;
+        };
+        let param = ParamAttr::parse(attr)?;
+        let field_ident = field.ident.as_ref().expect("Named fields have an ident");
+        let ty = &field.ty;
+
+        let name = match &param.name {
+            Some(name) => name.value(),
+            None => title_case(&field_ident.to_string()),
+        };
+        let default = match &param.default {
+            Some(default) => quote!(#default),
+            None => quote!(defaults.#field_ident.clone()),
+        };
+        let value = if param.color {
+            quote!(crate::engine::param::ParamValue::Color(#default))
+        } else {
+            quote!(<#ty as crate::engine::param::ParamType>::into_param(#default))
+        };
+        let order = bundles.len() as u32;
+        let page = param.page.as_ref().map_or(String::new(), LitStr::value);
+        let meta = param.meta();
+        bundles.push(quote! {
+            {
+                #meta
+                crate::engine::param::ParamBundle {
+                    name: crate::engine::param::ParamName(#name.to_string()),
+                    value: #value,
+                    order: crate::engine::param::ParamOrder(#order),
+                    page: crate::engine::param::ParamPage(#page.to_string()),
+                    meta,
+                }
+            }
+        });
+        names.push(name);
+        idents.push(field_ident);
+        types.push(ty);
+    }
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics crate::engine::param::OpParams for #ident #ty_generics #where_clause {
+            fn params() -> Vec<crate::engine::param::ParamBundle> {
+                #[allow(unused_variables)]
+                let defaults = <Self as Default>::default();
+                vec![#(#bundles),*]
+            }
+
+            fn apply(
+                &mut self,
+                params: &[(&crate::engine::param::ParamName, &crate::engine::param::ParamValue)],
+            ) {
+                for (name, value) in params {
+                    match name.as_str() {
+                        #(#names => {
+                            let value = <#types as crate::engine::param::ParamType>::from_param(value);
+                            if let Some(value) = value {
+                                self.#idents = value;
+                            }
+                        })*
+                        _ => {}
+                    }
+                }
+            }
+
+            fn read(
+                params: &crate::engine::param::Params,
+                entity: bevy::prelude::Entity,
+            ) -> Self {
+                #[allow(unused_mut)]
+                let mut this = <Self as Default>::default();
+                #(
+                    let value = params
+                        .get(entity, #names)
+                        .and_then(<#types as crate::engine::param::ParamType>::from_param);
+                    if let Some(value) = value {
+                        this.#idents = value;
+                    }
+                )*
+                this
+            }
+        }
+    })
+}
+
+/// `color_a` to `Color A`.
+fn title_case(name: &str) -> String {
+    name.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
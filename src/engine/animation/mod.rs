@@ -0,0 +1,242 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::engine::param::{
+    follow_links, ParamExpression, ParamLayer, ParamLink, ParamValue, ScriptedParam,
+};
+use crate::Sets;
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Timeline>().add_systems(
+            Update,
+            (advance_timeline, animate)
+                .chain()
+                .in_set(Sets::Params)
+                // Linked params follow the animated value
+                .before(follow_links),
+        );
+    }
+}
+
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+// Components
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+/// How the value changes from a keyframe to the next one.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    /// Hold the value until the next keyframe.
+    Step,
+    /// Ease between the keyframes, along a timing curve from `(0, 0)` to `(1, 1)` with the
+    /// given control points.
+    Bezier(Vec2, Vec2),
+    /// Spherical interpolation, for rotations. Other values are interpolated linearly.
+    Slerp,
+}
+
+impl Interpolation {
+    pub const EASE: Interpolation =
+        Interpolation::Bezier(Vec2::new(0.42, 0.0), Vec2::new(0.58, 1.0));
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Interpolation::Linear => "Linear",
+            Interpolation::Step => "Step",
+            Interpolation::Bezier(..) => "Bezier",
+            Interpolation::Slerp => "Slerp",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Keyframe {
+    /// The time of the keyframe on the timeline, in seconds.
+    pub time: f32,
+    pub value: ParamValue,
+    /// How the value changes until the next keyframe.
+    #[serde(default)]
+    pub interpolation: Interpolation,
+}
+
+/// Keyframes that animate a param, sorted by time.
+#[derive(Component, Deref, DerefMut, Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Keyframes(pub Vec<Keyframe>);
+
+impl Keyframes {
+    /// Whether a value can be animated.
+    pub fn can_animate(value: &ParamValue) -> bool {
+        matches!(
+            value,
+            ParamValue::F32(_)
+                | ParamValue::U32(_)
+                | ParamValue::I32(_)
+                | ParamValue::Vec2(_)
+                | ParamValue::Vec3(_)
+                | ParamValue::Vec4(_)
+                | ParamValue::Color(_)
+                | ParamValue::Quat(_)
+        )
+    }
+
+    /// Add a keyframe, replacing any keyframe at the same time.
+    pub fn insert(&mut self, key: Keyframe) {
+        match self
+            .0
+            .iter_mut()
+            .find(|other| (other.time - key.time).abs() < f32::EPSILON)
+        {
+            Some(other) => other.value = key.value,
+            None => {
+                self.0.push(key);
+                self.sort();
+            }
+        }
+    }
+
+    pub fn sort(&mut self) {
+        self.0.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+
+    /// The value at a time. The first and last keyframes hold their value outside of the
+    /// animation.
+    pub fn sample(&self, time: f32) -> Option<ParamValue> {
+        let next = self.0.iter().position(|key| key.time > time);
+        let (from, to) = match next {
+            None => return self.0.last().map(|key| key.value.clone()),
+            Some(0) => return self.0.first().map(|key| key.value.clone()),
+            Some(next) => (&self.0[next - 1], &self.0[next]),
+        };
+
+        let t = (time - from.time) / (to.time - from.time);
+        let value = match from.interpolation {
+            Interpolation::Step => from.value.clone(),
+            Interpolation::Linear => lerp(&from.value, &to.value, t, false),
+            Interpolation::Bezier(p1, p2) => lerp(&from.value, &to.value, ease(p1, p2, t), false),
+            Interpolation::Slerp => lerp(&from.value, &to.value, t, true),
+        };
+        Some(value)
+    }
+}
+
+/// Interpolate between two values of the same type. Values that can't be interpolated hold the
+/// first value.
+pub fn lerp(from: &ParamValue, to: &ParamValue, t: f32, slerp: bool) -> ParamValue {
+    match (from, to) {
+        (ParamValue::F32(a), ParamValue::F32(b)) => ParamValue::F32(a + (b - a) * t),
+        (ParamValue::U32(a), ParamValue::U32(b)) => {
+            ParamValue::U32((*a as f32 + (*b as f32 - *a as f32) * t).round() as u32)
+        }
+        (ParamValue::I32(a), ParamValue::I32(b)) => {
+            ParamValue::I32((*a as f32 + (*b as f32 - *a as f32) * t).round() as i32)
+        }
+        (ParamValue::Vec2(a), ParamValue::Vec2(b)) => ParamValue::Vec2(a.lerp(*b, t)),
+        (ParamValue::Vec3(a), ParamValue::Vec3(b)) => ParamValue::Vec3(a.lerp(*b, t)),
+        (ParamValue::Vec4(a), ParamValue::Vec4(b)) => ParamValue::Vec4(a.lerp(*b, t)),
+        (ParamValue::Color(a), ParamValue::Color(b)) => ParamValue::Color(a.lerp(*b, t)),
+        (ParamValue::Quat(a), ParamValue::Quat(b)) if slerp => ParamValue::Quat(a.slerp(*b, t)),
+        (ParamValue::Quat(a), ParamValue::Quat(b)) => ParamValue::Quat(a.lerp(*b, t)),
+        _ => from.clone(),
+    }
+}
+
+/// Evaluate a timing curve at `x`, i.e. the progress through the keyframe.
+fn ease(p1: Vec2, p2: Vec2, x: f32) -> f32 {
+    let bezier = |a: f32, b: f32, t: f32| {
+        let u = 1.0 - t;
+        3.0 * u * u * t * a + 3.0 * u * t * t * b + t * t * t
+    };
+    let slope = |a: f32, b: f32, t: f32| {
+        let u = 1.0 - t;
+        3.0 * u * u * a + 6.0 * u * t * (b - a) + 3.0 * t * t * (1.0 - b)
+    };
+
+    // Find the curve parameter for x with a few newton steps
+    let mut t = x;
+    for _ in 0..8 {
+        let error = bezier(p1.x, p2.x, t) - x;
+        let slope = slope(p1.x, p2.x, t);
+        if error.abs() < 1e-5 || slope.abs() < 1e-6 {
+            break;
+        }
+        t = (t - error / slope).clamp(0.0, 1.0);
+    }
+    bezier(p1.y, p2.y, t)
+}
+
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+// Resources
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+/// The playhead that keyframes are sampled at. It advances with [Time<Virtual>], so pausing
+/// virtual time stops playback.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Timeline {
+    /// The current time, in seconds.
+    #[serde(skip)]
+    pub time: f32,
+    /// The length shown in the timeline, in seconds.
+    pub length: f32,
+    /// The range played back in a loop.
+    pub loop_start: f32,
+    pub loop_end: f32,
+    pub looping: bool,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self {
+            time: 0.0,
+            length: 10.0,
+            loop_start: 0.0,
+            loop_end: 10.0,
+            looping: true,
+        }
+    }
+}
+
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+// Systems
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+fn advance_timeline(time: Res<Time<Virtual>>, mut timeline: ResMut<Timeline>) {
+    if time.is_paused() {
+        return;
+    }
+
+    timeline.time += time.delta_seconds();
+    let (start, end) = (timeline.loop_start, timeline.loop_end);
+    if timeline.looping && end > start && timeline.time >= end {
+        timeline.time = start + (timeline.time - start) % (end - start);
+    }
+}
+
+/// Set animated params to their value at the playhead.
+fn animate(
+    timeline: Res<Timeline>,
+    mut params_q: Query<(
+        &Keyframes,
+        &mut ParamValue,
+        Has<ScriptedParam>,
+        Option<&ParamExpression>,
+        Has<ParamLink>,
+    )>,
+) {
+    for (keyframes, mut param, scripted, expression, linked) in params_q.iter_mut() {
+        // Links, expressions and scripts are above animation
+        if ParamLayer::driver(scripted, expression, linked, true) != Some(ParamLayer::Animation) {
+            continue;
+        }
+        let Some(value) = keyframes.sample(timeline.time) else {
+            continue;
+        };
+
+        if std::mem::discriminant(&value) == std::mem::discriminant(&*param) && *param != value {
+            *param = value;
+        }
+    }
+}
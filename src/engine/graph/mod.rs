@@ -7,7 +7,9 @@ use crate::Sets;
 use bevy::prelude::*;
 use bevy::utils::HashMap;
 use petgraph::adj::DefaultIx;
-use petgraph::graph::NodeIndex;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::{EdgeFiltered, EdgeRef};
+use petgraph::Direction;
 
 pub mod event;
 
@@ -38,13 +40,75 @@ pub struct GraphId(pub NodeIndex<DefaultIx>);
 #[derive(Component, Debug)]
 pub struct GraphNode;
 
-type Graph = petgraph::stable_graph::StableGraph<GraphNode, (usize, usize)>;
+/// Edges into this input port only order execution, and don't carry any data. I.e. from the out
+/// ops inside a container to the container.
+pub const INTERNAL_PORT: u8 = u8::MAX;
+
+/// Edges into this input port order execution after the ops that a linked param follows, see
+/// [crate::engine::param::ParamLink].
+pub const LINK_PORT: u8 = u8::MAX - 1;
+
+/// Edges are weighted by their `(output_port, input_port)`.
+type Graph = petgraph::stable_graph::StableGraph<GraphNode, (u8, u8)>;
 
 #[derive(Resource, Default)]
 pub struct GraphState {
     pub graph: Graph,
     pub entity_map: HashMap<NodeIndex, Entity>,
     pub layout: Layout,
+    /// The error that stopped the graph from executing, if any.
+    pub error: Option<GraphError>,
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum GraphError {
+    /// The graph has a cycle that doesn't pass through an op that reads the previous frame
+    #[error("Cycle through op {0}, use a feedback op to read from the previous frame")]
+    Cycle(String),
+}
+
+impl GraphState {
+    /// Find the edge from `output` to `input` between the given ports.
+    pub fn find_edge(
+        &self,
+        output: NodeIndex,
+        input: NodeIndex,
+        ports: (u8, u8),
+    ) -> Option<EdgeIndex> {
+        self.graph
+            .edges_connecting(output, input)
+            .find(|edge| *edge.weight() == ports)
+            .map(|edge| edge.id())
+    }
+
+    /// The ops connected to the inputs of a node.
+    pub fn inputs(&self, node: NodeIndex) -> impl Iterator<Item = Entity> + '_ {
+        self.graph
+            .neighbors_directed(node, Direction::Incoming)
+            .filter_map(|idx| self.entity_map.get(&idx).copied())
+    }
+
+    /// Sort the ops in execution order. Edges into delayed ops read from the previous frame and
+    /// are ignored. Returns an op on the cycle if the graph still has one.
+    pub fn execution_order(
+        &self,
+        is_delayed: impl Fn(Entity) -> bool,
+    ) -> Result<Vec<Entity>, Entity> {
+        let graph = EdgeFiltered::from_fn(&self.graph, |edge| {
+            !self
+                .entity_map
+                .get(&edge.target())
+                .is_some_and(|entity| is_delayed(*entity))
+        });
+        let sorted = petgraph::algo::toposort(&graph, None)
+            .map_err(|cycle| self.entity_map[&cycle.node_id()])?;
+
+        Ok(sorted
+            .iter()
+            .filter_map(|idx| self.entity_map.get(idx).copied())
+            .collect())
+    }
 }
 
 pub fn update_graph(
@@ -68,27 +132,52 @@ pub fn add_graph_ids(
 }
 
 pub fn handle_connect(
+    mut commands: Commands,
     mut graph_state: ResMut<GraphState>,
     graph_id_q: Query<&GraphId>,
     mut ev_connect: EventReader<Connect>,
 ) {
     for connect in ev_connect.read() {
-        let output = graph_id_q.get(connect.output).unwrap();
-        let input = graph_id_q.get(connect.input).unwrap();
-        graph_state.graph.add_edge(**output, **input, (0, 0));
+        let (Ok(output), Ok(input)) = (
+            graph_id_q.get(connect.output),
+            graph_id_q.get(connect.input),
+        ) else {
+            warn!("Ignoring connection to an op without a graph node");
+            continue;
+        };
+
+        let ports = (connect.output_port, connect.input_port);
+        // Scripts connect every frame, only notify the op about new edges
+        if graph_state.find_edge(**output, **input, ports).is_some() {
+            continue;
+        }
+
+        graph_state.graph.add_edge(**output, **input, ports);
+        commands.trigger_targets(*connect, connect.input);
     }
 }
 
 pub fn handle_disconnect(
+    mut commands: Commands,
     mut graph_state: ResMut<GraphState>,
     graph_id_q: Query<&GraphId>,
     mut ev_disconnect: EventReader<Disconnect>,
 ) {
     for disconnect in ev_disconnect.read() {
-        let output = graph_id_q.get(disconnect.output).unwrap();
-        let input = graph_id_q.get(disconnect.input).unwrap();
-        let edge = graph_state.graph.find_edge(**output, **input).unwrap();
+        let (Ok(output), Ok(input)) = (
+            graph_id_q.get(disconnect.output),
+            graph_id_q.get(disconnect.input),
+        ) else {
+            continue;
+        };
+
+        let ports = (disconnect.output_port, disconnect.input_port);
+        let Some(edge) = graph_state.find_edge(**output, **input, ports) else {
+            continue;
+        };
+
         graph_state.graph.remove_edge(edge);
+        commands.trigger_targets(*disconnect, disconnect.input);
     }
 }
 
@@ -0,0 +1,471 @@
+use bevy::prelude::*;
+use bevy_egui::EguiContexts;
+
+use crate::engine::animation::Keyframes;
+use crate::engine::graph::event::{Connect, Disconnect};
+use crate::engine::op::{despawn_op, OpName, OpRef};
+use crate::engine::param::{ParamExpression, ParamLink, ParamName, ParamValue};
+use crate::engine::project::{
+    restore_connections, restore_op, ConnectionData, OpData, ParamDataValue,
+};
+use crate::index::{CompositeIndex2, UniqueIndex};
+use crate::ui::graph::NodePosition;
+use crate::Sets;
+
+/// The maximum number of edits that can be undone.
+const MAX_HISTORY: usize = 256;
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<History>()
+            .add_event::<Undo>()
+            .add_event::<Redo>()
+            // Undo runs before the graph and ui handle this frame's connection events
+            .add_systems(
+                First,
+                (
+                    undo.run_if(on_event::<Undo>()),
+                    redo.run_if(on_event::<Redo>()),
+                )
+                    .chain(),
+            )
+            .add_systems(Update, (hotkeys, seal).chain().in_set(Sets::Ui));
+    }
+}
+
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+// Events
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+/// Revert the last edit.
+#[derive(Event, Default, Debug, Clone, Copy)]
+pub struct Undo;
+
+/// Re-apply the last reverted edit.
+#[derive(Event, Default, Debug, Clone, Copy)]
+pub struct Redo;
+
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+// Resources
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+/// An edit made to the network. Ops are referred to by name, since an op that is deleted and
+/// restored comes back as a different entity.
+#[derive(Debug, Clone)]
+pub enum Edit {
+    /// A param changed value.
+    Param {
+        op: OpName,
+        param: String,
+        from: ParamDataValue,
+        to: ParamDataValue,
+    },
+    /// A param's expression was set or removed.
+    Expression {
+        op: OpName,
+        param: String,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    /// A param was linked to another op's param, or unlinked.
+    Link {
+        op: OpName,
+        param: String,
+        from: Option<ParamLink>,
+        to: Option<ParamLink>,
+    },
+    /// A param's keyframes were edited.
+    Keyframes {
+        op: OpName,
+        param: String,
+        from: Option<Keyframes>,
+        to: Option<Keyframes>,
+    },
+    /// An input was connected, replacing the connection it previously had.
+    Connect {
+        connection: ConnectionData,
+        replaced: Option<ConnectionData>,
+    },
+    /// An input was disconnected.
+    Disconnect(ConnectionData),
+    /// An op was created, along with the connections into it.
+    Create {
+        op: OpData,
+        connections: Vec<ConnectionData>,
+    },
+    /// An op was deleted, along with its connections.
+    Delete {
+        op: OpData,
+        connections: Vec<ConnectionData>,
+    },
+    /// An op's node was moved on the grid.
+    Move { op: OpName, from: Vec2, to: Vec2 },
+    /// A command run from the script repl, which can't be undone.
+    Script(String),
+    /// Edits made together, i.e. deleting several ops, which are undone as a single step.
+    Batch(Vec<Edit>),
+}
+
+impl Edit {
+    /// Whether the edit can be reverted.
+    pub fn is_undoable(&self) -> bool {
+        match self {
+            Edit::Script(_) => false,
+            Edit::Batch(edits) => edits.iter().all(Edit::is_undoable),
+            _ => true,
+        }
+    }
+
+    /// Merge a following edit into this one, i.e. the frames of a slider drag. Returns false if
+    /// the edits are unrelated.
+    fn merge(&mut self, next: &Edit) -> bool {
+        match (self, next) {
+            (
+                Edit::Param { op, param, to, .. },
+                Edit::Param {
+                    op: next_op,
+                    param: next_param,
+                    to: next_to,
+                    ..
+                },
+            ) if op == next_op && param == next_param => {
+                *to = next_to.clone();
+                true
+            }
+            (
+                Edit::Expression { op, param, to, .. },
+                Edit::Expression {
+                    op: next_op,
+                    param: next_param,
+                    to: next_to,
+                    ..
+                },
+            ) if op == next_op && param == next_param => {
+                *to = next_to.clone();
+                true
+            }
+            (
+                Edit::Link { op, param, to, .. },
+                Edit::Link {
+                    op: next_op,
+                    param: next_param,
+                    to: next_to,
+                    ..
+                },
+            ) if op == next_op && param == next_param => {
+                *to = next_to.clone();
+                true
+            }
+            (
+                Edit::Keyframes { op, param, to, .. },
+                Edit::Keyframes {
+                    op: next_op,
+                    param: next_param,
+                    to: next_to,
+                    ..
+                },
+            ) if op == next_op && param == next_param => {
+                *to = next_to.clone();
+                true
+            }
+            (
+                Edit::Move { op, to, .. },
+                Edit::Move {
+                    op: next_op,
+                    to: next_to,
+                    ..
+                },
+            ) if op == next_op => {
+                *to = *next_to;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn apply(&self, world: &mut World) {
+        match self {
+            Edit::Param { op, param, to, .. } => set_param(world, op, param, to),
+            Edit::Expression { op, param, to, .. } => set_expression(world, op, param, to),
+            Edit::Link { op, param, to, .. } => set_link(world, op, param, to),
+            Edit::Keyframes { op, param, to, .. } => set_keyframes(world, op, param, to),
+            Edit::Connect { connection, .. } => connect(world, connection),
+            Edit::Disconnect(connection) => disconnect(world, connection),
+            Edit::Create { op, connections } => restore(world, op, connections),
+            Edit::Delete { op, .. } => delete(world, &op.name),
+            Edit::Move { op, to, .. } => move_node(world, op, *to),
+            Edit::Script(_) => {}
+            Edit::Batch(edits) => edits.iter().for_each(|edit| edit.apply(world)),
+        }
+    }
+
+    fn revert(&self, world: &mut World) {
+        match self {
+            Edit::Param {
+                op, param, from, ..
+            } => set_param(world, op, param, from),
+            Edit::Expression {
+                op, param, from, ..
+            } => set_expression(world, op, param, from),
+            Edit::Link {
+                op, param, from, ..
+            } => set_link(world, op, param, from),
+            Edit::Keyframes {
+                op, param, from, ..
+            } => set_keyframes(world, op, param, from),
+            // Connecting the replaced output disconnects the new one
+            Edit::Connect {
+                connection,
+                replaced,
+            } => match replaced {
+                Some(replaced) => connect(world, replaced),
+                None => disconnect(world, connection),
+            },
+            Edit::Disconnect(connection) => connect(world, connection),
+            Edit::Create { op, .. } => delete(world, &op.name),
+            Edit::Delete { op, connections } => restore(world, op, connections),
+            Edit::Move { op, from, .. } => move_node(world, op, *from),
+            Edit::Script(_) => {}
+            Edit::Batch(edits) => edits.iter().rev().for_each(|edit| edit.revert(world)),
+        }
+    }
+}
+
+/// Edits that can be undone and redone. Edits are only recorded from the ui, as scripts set
+/// their params every frame and own whatever they create. Commands from the repl are recorded,
+/// but can't be undone.
+#[derive(Resource, Default, Debug)]
+pub struct History {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    /// Whether the last edit is finished, otherwise following edits to the same target are
+    /// merged into it.
+    sealed: bool,
+}
+
+impl History {
+    pub fn record(&mut self, edit: Edit) {
+        self.redo.clear();
+        if !self.sealed {
+            if let Some(last) = self.undo.last_mut() {
+                if last.merge(&edit) {
+                    return;
+                }
+            }
+        }
+
+        self.undo.push(edit);
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.remove(0);
+        }
+        self.sealed = false;
+    }
+
+    /// Finish the last edit, so the next edit is recorded as a separate step.
+    pub fn seal(&mut self) {
+        self.sealed = true;
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.undo.iter().any(Edit::is_undoable)
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+// Systems
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+fn hotkeys(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut egui_contexts: EguiContexts,
+    mut ev_undo: EventWriter<Undo>,
+    mut ev_redo: EventWriter<Redo>,
+) {
+    // Text fields have their own undo
+    if egui_contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+
+    let modifier = keys.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ]);
+    if !modifier || !keys.just_pressed(KeyCode::KeyZ) {
+        return;
+    }
+
+    if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        ev_redo.send(Redo);
+    } else {
+        ev_undo.send(Undo);
+    }
+}
+
+/// Drags are merged into a single edit until the mouse is released.
+fn seal(mouse: Res<ButtonInput<MouseButton>>, mut history: ResMut<History>) {
+    if mouse.get_just_released().next().is_some() {
+        history.seal();
+    }
+}
+
+fn undo(world: &mut World) {
+    let mut history = world.resource_mut::<History>();
+    history.seal();
+    let edit = loop {
+        match history.undo.pop() {
+            Some(edit) if edit.is_undoable() => break edit,
+            Some(Edit::Script(command)) => info!("Can't undo script command {}", command),
+            Some(_) => {}
+            None => return,
+        }
+    };
+    history.redo.push(edit.clone());
+
+    edit.revert(world);
+}
+
+fn redo(world: &mut World) {
+    let mut history = world.resource_mut::<History>();
+    history.seal();
+    let Some(edit) = history.redo.pop() else {
+        return;
+    };
+    history.undo.push(edit.clone());
+
+    edit.apply(world);
+}
+
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+// Helpers
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+fn find_op(world: &World, name: &str) -> Option<Entity> {
+    world
+        .resource::<UniqueIndex<OpName>>()
+        .get(&OpName(name.to_string()))
+        .copied()
+}
+
+fn find_param(world: &World, op: &OpName, param: &str) -> Option<Entity> {
+    let op = find_op(world, &op.0)?;
+    world
+        .resource::<CompositeIndex2<OpRef, ParamName>>()
+        .get(&(OpRef(op), ParamName(param.to_string())))
+        .copied()
+}
+
+fn set_param(world: &mut World, op: &OpName, param: &str, value: &ParamDataValue) {
+    let Some(param) = find_param(world, op, param) else {
+        return;
+    };
+
+    world.resource_scope(|world, op_name_idx: Mut<UniqueIndex<OpName>>| {
+        if let Some(mut param) = world.get_mut::<ParamValue>(param) {
+            value.apply(&mut param, &op_name_idx);
+        }
+    });
+}
+
+fn set_expression(world: &mut World, op: &OpName, param: &str, expression: &Option<String>) {
+    let Some(param) = find_param(world, op, param) else {
+        return;
+    };
+
+    match expression {
+        Some(expression) => {
+            world
+                .entity_mut(param)
+                .insert(ParamExpression(expression.clone()));
+        }
+        None => {
+            world.entity_mut(param).remove::<ParamExpression>();
+        }
+    }
+}
+
+fn set_link(world: &mut World, op: &OpName, param: &str, link: &Option<ParamLink>) {
+    let Some(param) = find_param(world, op, param) else {
+        return;
+    };
+
+    match link {
+        Some(link) => {
+            world.entity_mut(param).insert(link.clone());
+        }
+        None => {
+            world.entity_mut(param).remove::<ParamLink>();
+        }
+    }
+}
+
+fn set_keyframes(world: &mut World, op: &OpName, param: &str, keyframes: &Option<Keyframes>) {
+    let Some(param) = find_param(world, op, param) else {
+        return;
+    };
+
+    match keyframes {
+        Some(keyframes) => {
+            world.entity_mut(param).insert(keyframes.clone());
+        }
+        None => {
+            world.entity_mut(param).remove::<Keyframes>();
+        }
+    }
+}
+
+fn connect(world: &mut World, connection: &ConnectionData) {
+    let (Some(output), Some(input)) = (
+        find_op(world, &connection.output),
+        find_op(world, &connection.input),
+    ) else {
+        return;
+    };
+
+    world.send_event(Connect {
+        output,
+        input,
+        output_port: connection.output_port,
+        input_port: connection.input_port,
+    });
+}
+
+fn disconnect(world: &mut World, connection: &ConnectionData) {
+    let (Some(output), Some(input)) = (
+        find_op(world, &connection.output),
+        find_op(world, &connection.input),
+    ) else {
+        return;
+    };
+
+    world.send_event(Disconnect {
+        output,
+        input,
+        output_port: connection.output_port,
+        input_port: connection.input_port,
+    });
+}
+
+fn restore(world: &mut World, op: &OpData, connections: &[ConnectionData]) {
+    restore_op(world, op.clone());
+    restore_connections(world, connections.iter().cloned());
+}
+
+fn delete(world: &mut World, name: &str) {
+    if let Some(op) = find_op(world, name) {
+        despawn_op(world, op);
+    }
+}
+
+fn move_node(world: &mut World, op: &OpName, position: Vec2) {
+    if let Some(op) = find_op(world, &op.0) {
+        world.entity_mut(op).insert(NodePosition(position));
+    }
+}
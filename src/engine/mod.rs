@@ -1,8 +1,12 @@
 use bevy::prelude::*;
 
+pub mod animation;
 pub mod graph;
+pub mod history;
 pub mod op;
 pub mod param;
+pub mod preset;
+pub mod project;
 pub mod render;
 pub mod script;
 
@@ -13,9 +17,13 @@ impl Plugin for SepiascrapedEnginePlugin {
         app.add_plugins((
             script::ScriptPlugin,
             param::ParamPlugin,
+            animation::AnimationPlugin,
+            preset::PresetPlugin,
             graph::GraphPlugin,
+            history::HistoryPlugin,
             render::RenderPlugin,
             op::OpsPlugin,
+            project::ProjectPlugin,
         ));
     }
 }
@@ -107,6 +107,8 @@ impl Op for ComponentOpCamera {
     const INPUTS: usize = 0;
     const OUTPUTS: usize = 0;
     const CATEGORY: &'static str = CATEGORY;
+    const NAME: &'static str = "camera";
+    const DISPLAY_NAME: &'static str = "Camera";
 
     type OpType = OpType<ComponentOpCamera>;
 }
@@ -193,6 +193,8 @@ impl Op for ComponentOpGeom {
     const INPUTS: usize = 1;
     const OUTPUTS: usize = 0;
     const CATEGORY: &'static str = CATEGORY;
+    const NAME: &'static str = "geom";
+    const DISPLAY_NAME: &'static str = "Geom";
 
     type OpType = OpType<ComponentOpGeom>;
 }
@@ -104,6 +104,8 @@ impl Op for ComponentOpLight {
     const INPUTS: usize = 0;
     const OUTPUTS: usize = 0;
     const CATEGORY: &'static str = CATEGORY;
+    const NAME: &'static str = "light";
+    const DISPLAY_NAME: &'static str = "Light";
 
     type OpType = OpType<ComponentOpLight>;
 }
@@ -189,5 +189,7 @@ impl OpOnDisconnect for ComponentOpWindow {
 
 impl Op for ComponentOpWindow {
     const CATEGORY: &'static str = CATEGORY;
+    const NAME: &'static str = "window";
+    const DISPLAY_NAME: &'static str = "Window";
     type OpType = OpType<ComponentOpWindow>;
 }
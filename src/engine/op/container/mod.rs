@@ -0,0 +1,203 @@
+use bevy::prelude::*;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+
+use crate::engine::graph::event::{Connect, Disconnect};
+use crate::engine::graph::{GraphId, GraphState, INTERNAL_PORT};
+use crate::engine::op::container::types::base::ContainerOpBasePlugin;
+use crate::engine::op::texture::types::input::TextureInSettings;
+use crate::engine::op::texture::types::output::TextureOutSettings;
+use crate::engine::op::{OpImage, OpInputs, OpName, OpOutputs};
+use crate::Sets;
+
+pub mod types;
+
+pub const CATEGORY: &str = "Container";
+
+pub struct ContainerPlugin;
+
+impl Plugin for ContainerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ContainerOpBasePlugin)
+            .add_systems(Update, update_container_ports.in_set(Sets::Graph));
+    }
+}
+
+/// The in and out ops inside a container, in port order. Container ports only carry textures.
+#[derive(Component, Clone, Default, Debug)]
+pub struct ContainerPorts {
+    pub inputs: Vec<Entity>,
+    pub outputs: Vec<Entity>,
+}
+
+/// Marks an op that outputs one of its container's inputs.
+#[derive(Component, Clone, Copy, Default, Debug)]
+pub struct ContainerInput;
+
+/// Marks an op that provides one of its container's outputs.
+#[derive(Component, Clone, Copy, Default, Debug)]
+pub struct ContainerOutput;
+
+/// The image an op outputs on a port, looking through containers to the out op on that port.
+pub fn output_image(
+    op: Entity,
+    port: u8,
+    image_q: &Query<&OpImage>,
+    ports_q: &Query<&ContainerPorts>,
+) -> Option<Handle<Image>> {
+    let op = match ports_q.get(op) {
+        Ok(ports) => *ports.outputs.get(port as usize)?,
+        Err(_) => op,
+    };
+    image_q.get(op).ok().map(|image| image.0.clone())
+}
+
+/// Keep each container's ports in sync with the in and out ops inside of it, and route whatever
+/// is connected to the container through to its in ops.
+fn update_container_ports(
+    mut commands: Commands,
+    mut graph_state: ResMut<GraphState>,
+    mut ev_connect: EventWriter<Connect>,
+    mut ev_disconnect: EventWriter<Disconnect>,
+    mut container_q: Query<(
+        Entity,
+        &OpName,
+        &GraphId,
+        &mut OpInputs,
+        &mut OpOutputs,
+        &mut ContainerPorts,
+        &mut OpImage,
+    )>,
+    input_q: Query<
+        (Entity, &OpName, &TextureInSettings, &OpInputs),
+        (With<ContainerInput>, Without<ContainerPorts>),
+    >,
+    output_q: Query<
+        (Entity, &OpName, &TextureOutSettings, &GraphId, Ref<OpImage>),
+        (With<ContainerOutput>, Without<ContainerPorts>),
+    >,
+    downstream_q: Query<(Entity, &OpInputs), Without<ContainerPorts>>,
+) {
+    for (container, name, graph_id, mut inputs, mut outputs, mut ports, mut image) in
+        container_q.iter_mut()
+    {
+        let in_container = |op_name: &OpName| op_name.network() == Some(name.0.as_str());
+
+        let mut new_inputs = input_q
+            .iter()
+            .filter(|(_, op_name, _, _)| in_container(op_name))
+            .map(|(entity, op_name, settings, _)| (settings.port, op_name.clone(), entity))
+            .collect::<Vec<_>>();
+        new_inputs.sort();
+        let new_inputs = new_inputs
+            .into_iter()
+            .map(|(_, _, entity)| entity)
+            .collect::<Vec<_>>();
+
+        let mut new_outputs = output_q
+            .iter()
+            .filter(|(_, op_name, _, _, _)| in_container(op_name))
+            .map(|(entity, op_name, settings, _, _)| (settings.port, op_name.clone(), entity))
+            .collect::<Vec<_>>();
+        new_outputs.sort();
+        let new_outputs = new_outputs
+            .into_iter()
+            .map(|(_, _, entity)| entity)
+            .collect::<Vec<_>>();
+
+        if ports.inputs != new_inputs {
+            inputs.count = new_inputs.len();
+            ports.inputs = new_inputs;
+        }
+
+        let mut outputs_changed = false;
+        if ports.outputs != new_outputs {
+            outputs.count = new_outputs.len();
+            ports.outputs = new_outputs;
+            outputs_changed = true;
+
+            // Ops reading from the container have to execute after its out ops
+            let stale = graph_state
+                .graph
+                .edges_directed(**graph_id, Direction::Incoming)
+                .filter(|edge| edge.weight().1 == INTERNAL_PORT)
+                .map(|edge| edge.id())
+                .collect::<Vec<_>>();
+            for edge in stale {
+                graph_state.graph.remove_edge(edge);
+            }
+            for output in ports.outputs.iter() {
+                if let Ok((_, _, _, output_id, _)) = output_q.get(*output) {
+                    graph_state
+                        .graph
+                        .add_edge(**output_id, **graph_id, (0, INTERNAL_PORT));
+                }
+            }
+        }
+
+        let mut images = ports
+            .outputs
+            .iter()
+            .filter_map(|output| output_q.get(*output).ok())
+            .map(|(_, _, _, _, image)| image);
+        outputs_changed |= images.any(|image| image.is_changed());
+
+        // Show the first output on the container's node
+        if let Some((_, _, _, _, first)) = ports
+            .outputs
+            .first()
+            .and_then(|output| output_q.get(*output).ok())
+        {
+            if image.0 != first.0 {
+                image.0 = first.0.clone();
+            }
+        }
+
+        // Ops reading from the container need to pick up the images of its new out ops
+        if outputs_changed {
+            for (entity, op_inputs) in downstream_q.iter() {
+                for (input_port, (output, output_port)) in op_inputs.connections.iter() {
+                    if *output == container {
+                        commands.trigger_targets(
+                            Connect {
+                                output: container,
+                                input: entity,
+                                output_port: *output_port,
+                                input_port: *input_port,
+                            },
+                            entity,
+                        );
+                    }
+                }
+            }
+        }
+
+        // Connect whatever is connected to the container's inputs to its in ops
+        for (port, input) in ports.inputs.iter().enumerate() {
+            let Ok((_, _, _, in_inputs)) = input_q.get(*input) else {
+                continue;
+            };
+            let connection = inputs.connections.get(&(port as u8)).copied();
+            let current = in_inputs.connections.get(&0).copied();
+            match (connection, current) {
+                (Some(connection), current) if Some(connection) != current => {
+                    ev_connect.send(Connect {
+                        output: connection.0,
+                        input: *input,
+                        output_port: connection.1,
+                        input_port: 0,
+                    });
+                }
+                (None, Some(current)) => {
+                    ev_disconnect.send(Disconnect {
+                        output: current.0,
+                        input: *input,
+                        output_port: current.1,
+                        input_port: 0,
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+}
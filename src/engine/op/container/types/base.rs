@@ -0,0 +1,98 @@
+use bevy::ecs::system::lifetimeless::*;
+use bevy::ecs::system::SystemParamItem;
+use bevy::prelude::*;
+use bevy::render::extract_component::ExtractComponent;
+
+use crate::engine::graph::event::{Connect, Disconnect};
+use crate::engine::op::container::{ContainerPorts, CATEGORY};
+use crate::engine::op::texture;
+use crate::engine::op::{
+    Op, OpDefaultImage, OpExecute, OpImage, OpInputs, OpOnConnect, OpOnDisconnect, OpOutputs,
+    OpPlugin, OpShouldExecute, OpSpawn, OpType, OpUpdate,
+};
+use crate::engine::param::ParamBundle;
+
+#[derive(Default)]
+pub struct ContainerOpBasePlugin;
+
+impl Plugin for ContainerOpBasePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(OpPlugin::<ContainerOpBase>::default());
+    }
+}
+
+/// Holds the network of ops named after it. Its ports are created from the in and out ops inside
+/// of it.
+#[derive(Component, ExtractComponent, Clone, Default, Debug)]
+pub struct ContainerOpBase;
+
+impl OpSpawn for ContainerOpBase {
+    type Param = SRes<OpDefaultImage>;
+    type Bundle = (OpImage, OpInputs, OpOutputs, ContainerPorts);
+
+    fn params(bundle: &Self::Bundle) -> Vec<ParamBundle> {
+        vec![]
+    }
+
+    fn create_bundle<'w>(
+        entity: Entity,
+        default_image: &mut SystemParamItem<'w, '_, Self::Param>,
+    ) -> Self::Bundle {
+        (
+            OpImage(default_image.0.clone()),
+            OpInputs::new(Self::INPUTS).with_category(texture::CATEGORY),
+            OpOutputs {
+                count: Self::OUTPUTS,
+            },
+            ContainerPorts::default(),
+        )
+    }
+}
+
+impl OpUpdate for ContainerOpBase {
+    type Param = ();
+
+    fn update<'w>(entity: Entity, param: &mut SystemParamItem<'w, '_, Self::Param>) {}
+}
+
+impl OpShouldExecute for ContainerOpBase {
+    type Param = ();
+}
+
+impl OpExecute for ContainerOpBase {
+    fn execute(&self, entity: Entity, world: &mut World) {}
+}
+
+impl OpOnConnect for ContainerOpBase {
+    type Param = ();
+
+    fn on_connect<'w>(
+        entity: Entity,
+        event: Connect,
+        fully_connected: bool,
+        param: &mut SystemParamItem<'w, '_, Self::Param>,
+    ) {
+    }
+}
+
+impl OpOnDisconnect for ContainerOpBase {
+    type Param = ();
+
+    fn on_disconnect<'w>(
+        entity: Entity,
+        event: Disconnect,
+        fully_connected: bool,
+        param: &mut SystemParamItem<'w, '_, Self::Param>,
+    ) {
+    }
+}
+
+impl Op for ContainerOpBase {
+    const INPUTS: usize = 0;
+    const OUTPUTS: usize = 0;
+    const CATEGORY: &'static str = CATEGORY;
+    const NAME: &'static str = "container";
+    const DISPLAY_NAME: &'static str = "Container";
+
+    type OpType = OpType<ContainerOpBase>;
+}
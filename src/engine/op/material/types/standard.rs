@@ -190,5 +190,7 @@ impl OpOnDisconnect for MaterialOpStandard {
 
 impl Op for MaterialOpStandard {
     const CATEGORY: &'static str = CATEGORY;
+    const NAME: &'static str = "standard-material";
+    const DISPLAY_NAME: &'static str = "Standard Material";
     type OpType = OpType<MaterialOpStandard>;
 }
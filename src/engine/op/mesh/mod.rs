@@ -47,6 +47,30 @@ pub struct MeshOpBundle {
     outputs: OpOutputs,
 }
 
+/// Copy the mesh connected to a bypassed mesh op into its own mesh.
+pub fn passthrough(entity: Entity, world: &mut World) {
+    let Some(my_mesh) = world.get::<MeshOpHandle>(entity).cloned() else {
+        return;
+    };
+    let Some(inputs) = world.get::<OpInputs>(entity) else {
+        return;
+    };
+    let Some((input, _)) = inputs.connections.get(&0).copied() else {
+        return;
+    };
+    let Some(input_mesh) = world.get::<MeshOpHandle>(input).cloned() else {
+        return;
+    };
+
+    let mut meshes = world.resource_mut::<Assets<Mesh>>();
+    let Some(input_mesh) = meshes.get(&input_mesh.0).cloned() else {
+        return;
+    };
+    if let Some(mesh) = meshes.get_mut(&my_mesh.0) {
+        *mesh = input_mesh;
+    }
+}
+
 pub trait MeshExt {
     fn points(&self) -> &[[f32; 3]];
     fn points_mut(&mut self) -> &mut Vec<[f32; 3]>;
@@ -175,5 +175,7 @@ impl Op for MeshOpCuboid {
     const INPUTS: usize = 0;
     const OUTPUTS: usize = 1;
     const CATEGORY: &'static str = CATEGORY;
+    const NAME: &'static str = "cuboid";
+    const DISPLAY_NAME: &'static str = "Cuboid";
     type OpType = OpType<MeshOpCuboid>;
 }
@@ -178,6 +178,8 @@ impl Op for MeshOpGrid {
     const INPUTS: usize = 0;
     const OUTPUTS: usize = 1;
     const CATEGORY: &'static str = CATEGORY;
+    const NAME: &'static str = "grid";
+    const DISPLAY_NAME: &'static str = "Grid";
     type OpType = OpType<MeshOpGrid>;
 }
 
@@ -16,7 +16,7 @@ use crate::engine::op::{
     Op, OpExecute, OpImage, OpInputs, OpOnConnect, OpOnDisconnect, OpOutputs, OpPlugin, OpRef,
     OpShouldExecute, OpSpawn, OpType, OpUpdate,
 };
-use crate::engine::param::{IntoParams, ParamBundle, ParamName, ParamOrder, ParamValue, Params};
+use crate::engine::param::{IntoParams, OpParams, ParamBundle, ParamValue, Params};
 use crate::render_layers::RenderLayerManager;
 
 #[derive(Default)]
@@ -31,6 +31,15 @@ impl Plugin for MeshOpNoisePlugin {
 #[derive(Component, ExtractComponent, Clone, Default, Debug)]
 pub struct MeshOpNoise;
 
+/// The params of the op, besides its transform.
+#[derive(OpParams, Default, Debug)]
+struct MeshNoiseParams {
+    #[param(default = 0.1, soft_min = 0.0, soft_max = 1.0)]
+    strength: f32,
+    #[param]
+    seed: u32,
+}
+
 impl OpSpawn for MeshOpNoise {
     type Param = (
         SCommands,
@@ -106,20 +115,7 @@ impl OpSpawn for MeshOpNoise {
 
     fn params(bundle: &Self::Bundle) -> Vec<ParamBundle> {
         [
-            vec![
-                ParamBundle {
-                    name: ParamName("Strength".to_string()),
-                    value: ParamValue::F32(0.1),
-                    order: ParamOrder(0),
-                    ..default()
-                },
-                ParamBundle {
-                    name: ParamName("Seed".to_string()),
-                    value: ParamValue::U32(0),
-                    order: ParamOrder(1),
-                    ..default()
-                },
-            ],
+            MeshNoiseParams::params(),
             bundle.0.pbr.transform.as_params(),
         ]
         .concat()
@@ -159,8 +155,7 @@ impl OpExecute for MeshOpNoise {
     fn execute(&self, entity: Entity, world: &mut World) {
         let mut params = SystemState::<Params>::new(world);
         let mut params = params.get_mut(world);
-        let seed = params.get(entity, "Seed").unwrap().as_u32();
-        let strength = params.get(entity, "Strength").unwrap().as_f32();
+        let MeshNoiseParams { strength, seed } = params.read(entity);
 
         let inputs = world.entity(entity).get::<OpInputs>().unwrap();
         if !inputs.is_fully_connected() {
@@ -223,6 +218,8 @@ impl Op for MeshOpNoise {
     const INPUTS: usize = 1;
     const OUTPUTS: usize = 1;
     const CATEGORY: &'static str = CATEGORY;
+    const NAME: &'static str = "mesh-noise";
+    const DISPLAY_NAME: &'static str = "Noise";
 
     type OpType = OpType<MeshOpNoise>;
 }
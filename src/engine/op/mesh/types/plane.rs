@@ -175,5 +175,7 @@ impl Op for MeshOpPlane {
     const INPUTS: usize = 0;
     const OUTPUTS: usize = 1;
     const CATEGORY: &'static str = CATEGORY;
+    const NAME: &'static str = "plane";
+    const DISPLAY_NAME: &'static str = "Plane";
     type OpType = OpType<MeshOpPlane>;
 }
@@ -1,15 +1,22 @@
-use bevy::color::palettes::css::{NAVY, PURPLE, SALMON, SILVER};
+use bevy::color::palettes::css::{GRAY, NAVY, PURPLE, SALMON, SILVER, TEAL};
 use std::fmt::Debug;
 use std::marker::PhantomData;
 
 use crate::engine::graph::event::{Connect, Disconnect};
-use crate::engine::graph::GraphState;
+use crate::engine::graph::{GraphError, GraphId, GraphState};
 use crate::engine::op::component::ComponentPlugin;
+use crate::engine::op::container::ContainerPlugin;
 use crate::engine::op::material::MaterialPlugin;
 use crate::engine::op::mesh::MeshPlugin;
+use crate::engine::op::registry::OpRegistry;
+use crate::engine::op::stats::{OpStats, OpStatsPlugin};
 use crate::engine::op::texture::TexturePlugin;
-use crate::engine::param::{validate, ParamBundle, ParamHash, Params};
+use crate::engine::param::{
+    validate, ParamBundle, ParamDefault, ParamHash, ParamProvenance, Params,
+};
+use crate::engine::script::asset::Script;
 use crate::index::UniqueIndexPlugin;
+use crate::ui::graph::UiRef;
 use crate::Sets;
 use bevy::ecs::system::{ReadOnlySystemParam, StaticSystemParam, SystemParam, SystemParamItem};
 use bevy::prelude::*;
@@ -17,11 +24,14 @@ use bevy::render::extract_component::ExtractComponent;
 use bevy::render::render_resource::{
     Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
 };
-use bevy::utils::HashMap;
+use bevy::utils::{HashMap, HashSet, Instant};
 
 pub mod component;
+pub mod container;
 pub mod material;
 pub mod mesh;
+pub mod registry;
+pub mod stats;
 pub mod texture;
 
 #[derive(Default)]
@@ -31,15 +41,25 @@ impl Plugin for OpsPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins((
             ComponentPlugin,
+            ContainerPlugin,
             MaterialPlugin,
             MeshPlugin,
             TexturePlugin,
+            OpStatsPlugin,
             UniqueIndexPlugin::<OpName>::default(),
         ))
-        .add_systems(Last, ensure_despawn);
+        .configure_sets(Update, ExecuteOps.in_set(Sets::Execute))
+        .add_systems(Update, execute_bypass_changed.in_set(Sets::Params))
+        .add_systems(Update, execute.in_set(ExecuteOps))
+        .add_systems(Last, (ensure_despawn, despawn_ops, clear_execute));
     }
 }
 
+/// Executes the ops that are dirty, in graph order. Systems that need the ops' output from this
+/// frame run after it.
+#[derive(SystemSet, Hash, PartialEq, Eq, Clone, Debug)]
+pub struct ExecuteOps;
+
 #[derive(Default)]
 pub struct OpPlugin<T: Op> {
     _marker: PhantomData<T>,
@@ -50,6 +70,9 @@ where
     T: Op + Component + ExtractComponent + Send + Sync + Debug + Default + 'static,
 {
     fn build(&self, app: &mut App) {
+        app.init_resource::<OpRegistry>();
+        app.world_mut().resource_mut::<OpRegistry>().register::<T>();
+
         app.add_systems(Update, apply_deferred.after(spawn::<T>))
             .insert_resource(AmbientLight {
                 color: Color::WHITE,
@@ -66,7 +89,6 @@ where
                         .chain()
                         .before(validate)
                         .in_set(Sets::Params),
-                    execute.in_set(Sets::Execute),
                 ),
             );
     }
@@ -78,29 +100,24 @@ pub struct OpRef(pub Entity);
 #[derive(Component, Clone, ExtractComponent, Default, Debug)]
 pub struct OpType<T: Debug + Sync + Send + 'static>(PhantomData<T>);
 
+/// The script type name of an op, i.e. `mesh-noise`. See [registry::OpRegistry].
 #[derive(Component, Clone, Debug, Default, Hash, Ord, PartialOrd, Eq, PartialEq)]
 pub struct OpTypeName(pub &'static str);
 
-impl<T> OpType<T>
-where
-    T: Debug + Sync + Send + 'static,
-{
-    pub fn name() -> &'static str {
-        std::any::type_name::<T>().split("::").nth(5).unwrap()
-    }
-}
-
 #[derive(Component, Default, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
 pub struct OpCategory(pub &'static str);
 
 impl OpCategory {
+    /// The color of the category's nodes, or gray for a category this crate doesn't know.
     pub fn to_color(&self) -> Color {
         match self.0 {
             "Component" => Color::from(SILVER),
+            "Container" => Color::from(TEAL),
             "Material" => Color::from(SALMON),
             "Mesh" => Color::from(NAVY),
             "Texture" => Color::from(PURPLE),
-            _ => panic!("Unknown category: {}", self.0),
+            // Categories added by other crates
+            _ => Color::from(GRAY),
         }
     }
 
@@ -108,6 +125,10 @@ impl OpCategory {
         self.0 == "Component"
     }
 
+    pub fn is_container(&self) -> bool {
+        self.0 == "Container"
+    }
+
     pub fn is_material(&self) -> bool {
         self.0 == "Material"
     }
@@ -147,6 +168,55 @@ impl OpInputs {
     }
 }
 
+/// Bypasses an op. Texture and mesh ops with a single input pass it through unchanged, every
+/// other op freezes its last output.
+#[derive(Component, ExtractComponent, Clone, Copy, Default, Debug)]
+pub struct OpBypass;
+
+/// Whether a bypassed op passes its input through, rather than freezing its last output.
+pub fn bypass_passes_through(category: &OpCategory, inputs: &OpInputs) -> bool {
+    inputs.count == 1 && (category.is_texture() || category.is_mesh())
+}
+
+/// Marks an op that is being deleted. Its connections are removed first, and it is despawned
+/// on the following frame once the graph and ui have seen them go.
+#[derive(Component, Clone, Copy, Default, Debug)]
+pub struct OpDespawn;
+
+/// Who an op belongs to, which decides how long it lives and whether it's saved with the
+/// project.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OpOwner {
+    /// Created by a script, either the project's entry script or one attached to an op. It's
+    /// despawned on the first frame the script doesn't create it, or when the script is unloaded.
+    Script(AssetId<Script>),
+    /// Created from the repl. It lives until it's deleted, but isn't saved.
+    Repl,
+    /// Created in the editor, loaded from the project or handed over with `persist!`. It lives
+    /// until it's deleted, and is saved with the project.
+    User,
+}
+
+impl OpOwner {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OpOwner::Script(_) => "Script",
+            OpOwner::Repl => "REPL",
+            OpOwner::User => "User",
+        }
+    }
+
+    /// Whether the op is saved with the project.
+    pub fn is_saved(&self) -> bool {
+        matches!(self, OpOwner::User)
+    }
+}
+
+/// Marks an op that reads its inputs from the previous frame. Edges into it are ignored when
+/// ordering execution, which lets it close a feedback loop.
+#[derive(Component, Clone, Copy, Default, Debug)]
+pub struct OpDelayedInputs;
+
 #[derive(Component, Default)]
 pub struct OpOutputs {
     pub count: usize,
@@ -172,6 +242,7 @@ impl OpImage {
                 mip_level_count: 1,
                 sample_count: 1,
                 usage: TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_SRC
                     | TextureUsages::COPY_DST
                     | TextureUsages::RENDER_ATTACHMENT,
                 view_formats: &[],
@@ -185,6 +256,8 @@ impl OpImage {
     }
 }
 
+/// Marks an op as dirty for this frame. Dirtiness flows downstream along the graph before
+/// execution.
 #[derive(Component, Clone, Debug, Default)]
 pub struct Execute;
 
@@ -230,15 +303,21 @@ fn spawn<'w, 's, T>(
             .insert((
                 OpCategory(T::CATEGORY),
                 OpDynExecute(Box::new(T::default())),
-                OpTypeName(OpType::<T>::name()),
+                OpTypeName(T::NAME),
                 ParamHash(0),
+                OpStats::default(),
                 bundle,
             ))
             .observe(on_connect::<T>)
             .observe(on_disconnect::<T>)
             .with_children(|parent| {
                 params.into_iter().for_each(|param| {
-                    parent.spawn((OpRef(parent.parent_entity()), param));
+                    parent.spawn((
+                        OpRef(parent.parent_entity()),
+                        ParamDefault(param.value.clone()),
+                        ParamProvenance::default(),
+                        param,
+                    ));
                 });
             });
     }
@@ -255,14 +334,16 @@ pub trait OpUpdate {
 }
 
 fn update<'w, 's, T>(
-    mut ops_q: Query<Entity, With<OpType<T>>>,
+    mut ops_q: Query<(Entity, &mut OpStats), With<OpType<T>>>,
     param: StaticSystemParam<<T as OpUpdate>::Param>,
 ) where
     T: Op + Component + Debug + Send + Sync + 'static,
 {
     let mut param = param.into_inner();
-    for entity in ops_q.iter_mut() {
+    for (entity, mut stats) in ops_q.iter_mut() {
+        let start = Instant::now();
         T::update(entity, &mut param);
+        stats.record_update(start.elapsed());
     }
 }
 
@@ -283,14 +364,16 @@ pub trait OpShouldExecute {
 
 fn should_execute<'w, 's, T>(
     mut commands: Commands,
-    mut ops_q: Query<(Entity, &mut ParamHash), With<OpType<T>>>,
+    mut ops_q: Query<(Entity, &mut ParamHash, &mut OpStats), With<OpType<T>>>,
     param: StaticSystemParam<<T as OpShouldExecute>::Param>,
     params: Params,
 ) where
     T: Op + Component + Debug + Send + Sync + 'static,
 {
     let mut param = param.into_inner();
-    for (entity, mut hash) in ops_q.iter_mut() {
+    for (entity, mut hash, mut stats) in ops_q.iter_mut() {
+        let start = Instant::now();
+
         // Update the hash and mark this op as execute if the parameters have changed.
         let new_hash = params.hash(entity);
         if hash.0 != new_hash {
@@ -302,6 +385,8 @@ fn should_execute<'w, 's, T>(
         if T::should_execute(entity, &mut param) {
             commands.entity(entity).insert(Execute);
         }
+
+        stats.record_should_execute(start.elapsed());
     }
 }
 
@@ -312,32 +397,115 @@ pub trait OpExecute {
     fn execute(&self, entity: Entity, world: &mut World);
 }
 
-fn execute(
+pub(crate) fn execute(
     world: &mut World,
     // graph_state: Res<GraphState>,
     // mut ops_q: Query<(&mut OpDynExecute), With<Execute>>
 ) {
+    let mut delayed_q = world.query_filtered::<Entity, With<OpDelayedInputs>>();
+    let delayed = delayed_q.iter(world).collect::<HashSet<Entity>>();
+    let mut frozen_q =
+        world.query_filtered::<(Entity, &OpCategory, Option<&OpInputs>), With<OpBypass>>();
+    let frozen = frozen_q
+        .iter(world)
+        .filter(|(_, category, inputs)| {
+            !inputs.is_some_and(|inputs| bypass_passes_through(category, inputs))
+        })
+        .map(|(entity, _, _)| entity)
+        .collect::<HashSet<Entity>>();
+    // Frozen ops keep their last output, so they never execute
+    for entity in frozen.iter() {
+        world.entity_mut(*entity).remove::<Execute>();
+    }
+    let mut dirty_q = world.query_filtered::<Entity, With<Execute>>();
+    let mut dirty = dirty_q.iter(world).collect::<HashSet<Entity>>();
     let graph_state = world.get_resource::<GraphState>().unwrap();
-    let sorted =
-        petgraph::algo::toposort(&graph_state.graph, None).expect("There should not be a cycle");
-    let entities = sorted
-        .iter()
-        .map(|idx| graph_state.entity_map[idx].clone())
-        .collect::<Vec<Entity>>();
+    let sorted = graph_state.execution_order(|entity| delayed.contains(&entity));
+    let entities = match sorted {
+        Ok(entities) => {
+            world.resource_mut::<GraphState>().error = None;
+            entities
+        }
+        Err(entity) => {
+            let name = world
+                .get::<OpName>(entity)
+                .map_or_else(|| format!("{entity:?}"), |name| name.0.clone());
+            let err = GraphError::Cycle(name);
+            let mut graph_state = world.resource_mut::<GraphState>();
+            if graph_state.error.as_ref() != Some(&err) {
+                error!("{}", err);
+                graph_state.error = Some(err);
+            }
+            return;
+        }
+    };
+
+    // Anything downstream of a dirty op is dirty too. Delayed ops read the previous frame,
+    // so they don't inherit dirtiness from their inputs.
+    let graph_state = world.resource::<GraphState>();
+    let mut newly_dirty = vec![];
+    for entity in entities.iter() {
+        if dirty.contains(entity) || delayed.contains(entity) || frozen.contains(entity) {
+            continue;
+        }
+        let Some(graph_id) = world.get::<GraphId>(*entity) else {
+            continue;
+        };
+        if graph_state
+            .inputs(**graph_id)
+            .any(|input| dirty.contains(&input))
+        {
+            dirty.insert(*entity);
+            newly_dirty.push(*entity);
+        }
+    }
+    for entity in newly_dirty {
+        world.entity_mut(entity).insert(Execute);
+    }
 
+    let now = world.resource::<Time>().elapsed_seconds();
     unsafe {
         let world_cell = world.as_unsafe_world_cell();
         let mut ops_q = world_cell
             .world_mut()
-            .query_filtered::<&OpDynExecute, With<Execute>>();
+            .query_filtered::<(&OpDynExecute, Has<OpBypass>), With<Execute>>();
         for entity in entities {
-            if let Ok(mut op) = ops_q.get(world_cell.world(), entity) {
-                op.execute(entity, &mut world_cell.world_mut());
+            if let Ok((mut op, bypass)) = ops_q.get(world_cell.world(), entity) {
+                let start = Instant::now();
+                if bypass {
+                    // Textures are passed through by the render world
+                    mesh::passthrough(entity, &mut world_cell.world_mut());
+                } else {
+                    op.execute(entity, &mut world_cell.world_mut());
+                }
+                let elapsed = start.elapsed();
+                if let Some(mut stats) = world_cell.world_mut().get_mut::<OpStats>(entity) {
+                    stats.record_cook(elapsed, now);
+                }
             }
         }
     }
 }
 
+/// Ops need to execute when they are bypassed or restored.
+fn execute_bypass_changed(
+    mut commands: Commands,
+    added_q: Query<Entity, Added<OpBypass>>,
+    mut removed: RemovedComponents<OpBypass>,
+) {
+    for entity in added_q.iter().chain(removed.read()) {
+        if let Some(mut entity) = commands.get_entity(entity) {
+            entity.insert(Execute);
+        }
+    }
+}
+
+fn clear_execute(mut commands: Commands, execute_q: Query<Entity, With<Execute>>) {
+    for entity in execute_q.iter() {
+        commands.entity(entity).remove::<Execute>();
+    }
+}
+
 /// Handler for when a new connection event occurs in the ui.
 trait OpOnConnect {
     type Param: SystemParam + 'static;
@@ -353,6 +521,7 @@ trait OpOnConnect {
 
 fn on_connect<T>(
     mut trigger: Trigger<Connect>,
+    mut commands: Commands,
     mut op_q: Query<&mut OpInputs, With<OpType<T>>>,
     mut ev_disconnect: EventWriter<Disconnect>,
     param: StaticSystemParam<<T as OpOnConnect>::Param>,
@@ -362,7 +531,12 @@ fn on_connect<T>(
     let ev = trigger.event();
     let mut param = param.into_inner();
     if let Ok(mut input) = op_q.get_mut(ev.input) {
-        if let Some((prev, prev_port)) = input.connections.get(&ev.input_port) {
+        // An input port only has a single connection, so replace the previous one
+        if let Some((prev, prev_port)) = input
+            .connections
+            .get(&ev.input_port)
+            .filter(|prev| **prev != (ev.output, ev.output_port))
+        {
             ev_disconnect.send(Disconnect {
                 output: *prev,
                 input: ev.input,
@@ -374,6 +548,7 @@ fn on_connect<T>(
         input
             .connections
             .insert(ev.input_port, (ev.output, ev.output_port));
+        commands.entity(ev.input).insert(Execute);
         T::on_connect(ev.input, *ev, input.is_fully_connected(), &mut param);
     }
 }
@@ -393,6 +568,7 @@ trait OpOnDisconnect {
 
 fn on_disconnect<T>(
     trigger: Trigger<Disconnect>,
+    mut commands: Commands,
     mut op_q: Query<&mut OpInputs, With<OpType<T>>>,
     param: StaticSystemParam<<T as OpOnDisconnect>::Param>,
 ) where
@@ -401,7 +577,13 @@ fn on_disconnect<T>(
     let mut param = param.into_inner();
     let ev = trigger.event();
     if let Ok(mut input) = op_q.get_mut(ev.input) {
-        input.connections.retain(|k, (e, _)| e != &ev.output);
+        // The port may have already been replaced by a new connection
+        if input.connections.get(&ev.input_port) != Some(&(ev.output, ev.output_port)) {
+            return;
+        }
+
+        input.connections.remove(&ev.input_port);
+        commands.entity(ev.input).insert(Execute);
         T::on_disconnect(ev.input, *ev, input.is_fully_connected(), &mut param);
     }
 }
@@ -416,14 +598,111 @@ pub trait Op:
     const OUTPUTS: usize = 0;
     /// The category of this op.
     const CATEGORY: &'static str;
+    /// The script type name of this op, i.e. `mesh-noise`. Must be unique across all ops.
+    const NAME: &'static str;
+    /// The name of this op shown in the UI.
+    const DISPLAY_NAME: &'static str;
 
     /// The type of the op.
     type OpType: Debug + Component + ExtractComponent + Send + Sync + 'static;
 }
 
+/// The path of an op, i.e. `scene/noise1` for the op `noise1` inside the container `scene`. Ops
+/// in the root network have no container prefix.
 #[derive(Component, Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct OpName(pub String);
 
+impl OpName {
+    /// Separates the names of containers from the ops inside them.
+    pub const SEPARATOR: char = '/';
+
+    /// Resolve a path like `/scene/noise1`. Paths always start from the root network, so the
+    /// leading separator is optional.
+    pub fn from_path(path: &str) -> Self {
+        Self(path.trim_start_matches(Self::SEPARATOR).to_string())
+    }
+
+    /// The name of the op within its container, i.e. `noise1`.
+    pub fn base_name(&self) -> &str {
+        self.0
+            .rsplit_once(Self::SEPARATOR)
+            .map_or(self.0.as_str(), |(_, name)| name)
+    }
+
+    /// The path of the container the op is in, or `None` for the root network.
+    pub fn network(&self) -> Option<&str> {
+        self.0
+            .rsplit_once(Self::SEPARATOR)
+            .map(|(network, _)| network)
+    }
+
+    /// The first free name for a new op in a network, numbered after a base name, i.e. `ramp1`.
+    pub fn unique(network: Option<&str>, base: &str, is_taken: impl Fn(&OpName) -> bool) -> Self {
+        let prefix = network.map_or(String::new(), |network| {
+            format!("{}{}", network, Self::SEPARATOR)
+        });
+        (1..)
+            .map(|i| Self(format!("{}{}{}", prefix, base, i)))
+            .find(|name| !is_taken(name))
+            .unwrap()
+    }
+}
+
+/// Spawn a new op from its script type name. The op's bundle and params will be created
+/// by the [OpSpawn] systems.
+pub fn spawn_op<'w>(world: &'w mut World, ty: &str, name: OpName) -> Option<EntityWorldMut<'w>> {
+    let op = world.resource::<OpRegistry>().get(ty)?.clone();
+    Some(op.spawn(world, name))
+}
+
+/// Delete an op, disconnecting it from every op it is connected to.
+pub fn despawn_op(world: &mut World, entity: Entity) {
+    let mut inputs_q = world.query::<(Entity, &OpInputs)>();
+    let disconnects = inputs_q
+        .iter(world)
+        .flat_map(|(input, inputs)| {
+            inputs
+                .connections
+                .iter()
+                .filter(move |(_, (output, _))| input == entity || *output == entity)
+                .map(move |(input_port, (output, output_port))| Disconnect {
+                    output: *output,
+                    input,
+                    output_port: *output_port,
+                    input_port: *input_port,
+                })
+        })
+        .collect::<Vec<_>>();
+    world.send_event_batch(disconnects);
+
+    if let Some(mut entity) = world.get_entity_mut(entity) {
+        entity.insert(OpDespawn);
+    }
+}
+
+fn despawn_ops(
+    mut commands: Commands,
+    mut graph_state: ResMut<GraphState>,
+    despawn_q: Query<(Entity, Ref<OpDespawn>, Option<&GraphId>, Option<&UiRef>)>,
+) {
+    for (entity, despawn, graph_id, ui_ref) in despawn_q.iter() {
+        // Wait a frame for the disconnects to be handled
+        if despawn.is_added() {
+            continue;
+        }
+
+        if let Some(graph_id) = graph_id {
+            graph_state.graph.remove_node(**graph_id);
+            graph_state.entity_map.remove(&**graph_id);
+            graph_state.layout.remove(&**graph_id);
+        }
+        if let Some(ui_ref) = ui_ref {
+            commands.entity(ui_ref.0).despawn_recursive();
+        }
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
 fn ensure_despawn(
     mut commands: Commands,
     mut removed: RemovedComponents<OpName>,
@@ -0,0 +1,86 @@
+use std::fmt::Debug;
+
+use bevy::prelude::*;
+use bevy::render::extract_component::ExtractComponent;
+use bevy::utils::HashMap;
+
+use crate::engine::op::{Op, OpName, OpType};
+
+/// Describes a registered op type, so it can be listed and spawned without knowing its
+/// concrete type.
+#[derive(Clone, Debug)]
+pub struct OpRegistration {
+    /// The script type name of the op, i.e. `mesh-noise`.
+    pub name: &'static str,
+    /// The name of the op shown in the UI.
+    pub display_name: &'static str,
+    /// The category of the op.
+    pub category: &'static str,
+    /// The number of inputs the op provides.
+    pub inputs: usize,
+    /// The number of outputs the op provides.
+    pub outputs: usize,
+    spawn: fn(&mut World, OpName) -> EntityWorldMut<'_>,
+}
+
+impl OpRegistration {
+    fn new<T>() -> Self
+    where
+        T: Op + Component + ExtractComponent + Send + Sync + Debug + Default + 'static,
+    {
+        Self {
+            name: T::NAME,
+            display_name: T::DISPLAY_NAME,
+            category: T::CATEGORY,
+            inputs: T::INPUTS,
+            outputs: T::OUTPUTS,
+            spawn: |world, name| world.spawn((name, OpType::<T>::default())),
+        }
+    }
+
+    /// Spawn a new op of this type. The op's bundle and params will be created by the
+    /// [crate::engine::op::OpSpawn] systems.
+    pub fn spawn<'w>(&self, world: &'w mut World, name: OpName) -> EntityWorldMut<'w> {
+        (self.spawn)(world, name)
+    }
+}
+
+/// All op types, keyed by their script type name. Each [crate::engine::op::OpPlugin] registers
+/// its op here, so ops can be added without editing the engine.
+#[derive(Resource, Default, Debug)]
+pub struct OpRegistry {
+    ops: Vec<OpRegistration>,
+    by_name: HashMap<&'static str, usize>,
+}
+
+impl OpRegistry {
+    pub fn register<T>(&mut self)
+    where
+        T: Op + Component + ExtractComponent + Send + Sync + Debug + Default + 'static,
+    {
+        if self.by_name.contains_key(T::NAME) {
+            panic!("Op type {} is already registered", T::NAME);
+        }
+
+        self.by_name.insert(T::NAME, self.ops.len());
+        self.ops.push(OpRegistration::new::<T>());
+    }
+
+    /// Get an op type by its script type name.
+    pub fn get(&self, name: &str) -> Option<&OpRegistration> {
+        self.by_name.get(name).map(|idx| &self.ops[*idx])
+    }
+
+    /// All op types, in registration order.
+    pub fn iter(&self) -> impl Iterator<Item = &OpRegistration> {
+        self.ops.iter()
+    }
+
+    /// All op types in a category, in registration order.
+    pub fn by_category<'a>(
+        &'a self,
+        category: &'a str,
+    ) -> impl Iterator<Item = &'a OpRegistration> + 'a {
+        self.ops.iter().filter(move |op| op.category == category)
+    }
+}
@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use bevy::diagnostic::{DiagnosticPath, DiagnosticsStore};
+use bevy::prelude::*;
+use bevy::render::diagnostic::RenderDiagnosticsPlugin;
+
+use crate::engine::op::texture::render::TextureOpInputImages;
+use crate::engine::op::ExecuteOps;
+use crate::Sets;
+
+/// How much each new sample contributes to the running averages.
+const SMOOTHING: f32 = 0.1;
+
+pub struct OpStatsPlugin;
+
+impl Plugin for OpStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RenderDiagnosticsPlugin).add_systems(
+            Update,
+            update_gpu_stats.after(ExecuteOps).in_set(Sets::Execute),
+        );
+    }
+}
+
+/// Cook statistics for an op, recorded around its update, should execute and execute
+/// behaviour.
+#[derive(Component, Clone, Default, Debug)]
+pub struct OpStats {
+    /// The number of times the op has executed.
+    pub cook_count: u64,
+    /// The elapsed time in seconds when the op last executed.
+    pub last_cook: Option<f32>,
+    /// CPU time of the last execution.
+    pub last_cook_time: Duration,
+    /// Running average of the CPU time spent executing.
+    pub average_cook_time: Duration,
+    /// Running average of the CPU time spent in update and should execute each frame.
+    pub average_update_time: Duration,
+    /// Running average of the GPU time of the op's render pass, if the platform supports
+    /// timestamp queries.
+    pub gpu_time: Option<Duration>,
+    frame_update_time: Duration,
+}
+
+impl OpStats {
+    pub fn record_cook(&mut self, elapsed: Duration, now: f32) {
+        self.cook_count += 1;
+        self.last_cook = Some(now);
+        self.last_cook_time = elapsed;
+        self.average_cook_time = smooth(self.average_cook_time, elapsed);
+    }
+
+    /// Start recording this frame's update time.
+    pub fn record_update(&mut self, elapsed: Duration) {
+        self.frame_update_time = elapsed;
+    }
+
+    /// Finish recording this frame's update time.
+    pub fn record_should_execute(&mut self, elapsed: Duration) {
+        let frame_update_time = self.frame_update_time + elapsed;
+        self.average_update_time = smooth(self.average_update_time, frame_update_time);
+    }
+
+    /// The average time this op costs each frame.
+    pub fn total_time(&self) -> Duration {
+        self.average_cook_time + self.average_update_time + self.gpu_time.unwrap_or_default()
+    }
+}
+
+fn smooth(average: Duration, sample: Duration) -> Duration {
+    average.mul_f32(1.0 - SMOOTHING) + sample.mul_f32(SMOOTHING)
+}
+
+/// The diagnostic the render pass of a texture op records its timings to.
+pub fn gpu_diagnostic_path(entity: Entity) -> DiagnosticPath {
+    let span = texture_op_span(entity);
+    DiagnosticPath::from_components(["render", span.as_str(), "elapsed_gpu"])
+}
+
+/// The name of the render pass span of a texture op.
+pub fn texture_op_span(entity: Entity) -> String {
+    format!("texture_op_{}", entity.to_bits())
+}
+
+fn update_gpu_stats(
+    diagnostics: Res<DiagnosticsStore>,
+    mut op_q: Query<(Entity, &mut OpStats), With<TextureOpInputImages>>,
+) {
+    for (entity, mut stats) in op_q.iter_mut() {
+        let gpu_time = diagnostics
+            .get(&gpu_diagnostic_path(entity))
+            .and_then(|diagnostic| diagnostic.smoothed())
+            .map(|ms| Duration::from_secs_f64(ms / 1000.0));
+        if stats.gpu_time != gpu_time {
+            stats.gpu_time = gpu_time;
+        }
+    }
+}
@@ -11,22 +11,34 @@ use bevy::render::render_resource::encase::internal::WriteInto;
 use bevy::render::render_resource::{
     Extent3d, ShaderType, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
 };
+use bevy::render::RenderApp;
 use bevy::sprite::Material2d;
 use lifetimeless::SResMut;
 
 use types::composite::TextureOpCompositePlugin;
 use types::ramp::TextureOpRampPlugin;
 
-use crate::engine::op::texture::render::TextureOpInputImages;
+use crate::engine::op::container::{output_image, ContainerPorts};
+use crate::engine::op::texture::render::{TextureOpCooked, TextureOpInputImages};
+use crate::engine::op::texture::types::feedback::TextureOpFeedbackPlugin;
+use crate::engine::op::texture::types::input::TextureOpInPlugin;
 use crate::engine::op::texture::types::noise::TextureOpNoisePlugin;
-use crate::engine::op::{Op, OpDefaultImage, OpImage, OpInputs, OpOutputs};
-use crate::engine::param::{ParamBundle, ParamName, ParamValue};
+use crate::engine::op::texture::types::output::TextureOpOutPlugin;
+use crate::engine::op::{
+    Execute, ExecuteOps, Op, OpBypass, OpDefaultImage, OpImage, OpInputs, OpOutputs,
+};
+use crate::engine::param::{OpParams, ParamBundle, ParamMeta, ParamName, ParamValue};
+use crate::Sets;
 
 pub mod render;
 pub mod types;
 
 pub const CATEGORY: &str = "Texture";
 
+/// The order of the first texture op camera. Each op renders after the ops it depends on, see
+/// [crate::engine::render::order_cameras].
+pub const CAMERA_ORDER: isize = 3;
+
 pub struct TexturePlugin;
 
 impl Plugin for TexturePlugin {
@@ -35,12 +47,26 @@ impl Plugin for TexturePlugin {
             ExtractComponentPlugin::<TextureOpInputImages>::default(),
             ExtractComponentPlugin::<OpInputs>::default(),
             ExtractComponentPlugin::<OpImage>::default(),
+            ExtractComponentPlugin::<OpBypass>::default(),
             TextureOpRampPlugin,
             TextureOpCompositePlugin,
             TextureOpNoisePlugin,
+            TextureOpFeedbackPlugin,
+            TextureOpInPlugin,
+            TextureOpOutPlugin,
         ))
         .add_systems(Startup, setup)
+        .add_systems(
+            Update,
+            cook_op_cameras.after(ExecuteOps).in_set(Sets::Execute),
+        )
         .add_systems(Last, update_op_cameras);
+
+        let cooked = TextureOpCooked::default();
+        app.insert_resource(cooked.clone());
+        app.get_sub_app_mut(RenderApp)
+            .unwrap()
+            .insert_resource(cooked);
     }
 }
 
@@ -124,9 +150,9 @@ fn update<'w, T: TextureOp>(
     let params = children
         .iter()
         .filter_map(|entity| params_q.get(*entity).ok())
-        .collect();
+        .collect::<Vec<_>>();
 
-    T::update_uniform(&mut uniform, &params);
+    uniform.apply(&params);
 
     let resolution = params
         .iter()
@@ -163,7 +189,7 @@ fn create_bundle<'w, T: TextureOp>(
             camera: Camera3dBundle {
                 camera_render_graph: CameraRenderGraph::new(render::TextureOpSubGraph),
                 camera: Camera {
-                    order: 3,
+                    order: CAMERA_ORDER,
                     target: image.clone().into(),
                     ..default()
                 },
@@ -184,15 +210,18 @@ fn params<T: TextureOp>(bundle: &DefaultTextureBundle<T>) -> Vec<ParamBundle> {
         value: ParamValue::UVec2(UVec2::new(512, 512)),
         order: crate::engine::param::ParamOrder(0),
         page: crate::engine::param::ParamPage("Common".to_string()),
+        meta: ParamMeta::default().range(1.0, 8192.0).unit("px"),
         ..default()
     }];
 
-    [common_params, <T as TextureOp>::params()].concat()
+    [common_params, <T::Uniform as OpParams>::params()].concat()
 }
 
 type DefaultTextureOnConnectParam = (
     lifetimeless::SCommands,
-    SQuery<(Read<OpImage>, Write<TextureOpInputImages>)>,
+    SQuery<Read<OpImage>>,
+    SQuery<Read<ContainerPorts>>,
+    SQuery<Write<TextureOpInputImages>>,
 );
 
 type DefaultTextureOnDisconnectParam = (
@@ -207,11 +236,12 @@ fn on_connect<'w>(
     fully_connected: bool,
     param: &mut SystemParamItem<'w, '_, DefaultTextureOnConnectParam>,
 ) {
-    let (ref mut commands, ref mut op_q) = param;
-    let (new_image, _) = op_q.get(event.output).unwrap();
-    let new_image = new_image.0.clone();
-    let (_, mut my_images) = op_q.get_mut(entity).unwrap();
-    my_images.insert(event.output, new_image);
+    let (ref mut commands, ref image_q, ref ports_q, ref mut op_q) = param;
+    let Some(new_image) = output_image(event.output, event.output_port, image_q, ports_q) else {
+        return;
+    };
+    let mut my_images = op_q.get_mut(entity).unwrap();
+    my_images.insert(event.input_port, new_image);
 }
 
 fn on_disconnect<'w>(
@@ -222,13 +252,58 @@ fn on_disconnect<'w>(
 ) {
     let (ref mut commands, ref mut images, ref mut op_q) = param;
     let (my_image, mut my_images) = op_q.get_mut(entity).unwrap();
-    my_images.remove(&event.output);
+    my_images.remove(&event.input_port);
     if !fully_connected {
         let mut my_image = images.get_mut(&my_image.0).unwrap();
         *my_image = OpImage::new_image(my_image.width(), my_image.height());
     }
 }
 
+/// Texture op cameras only render when the op has executed, and keep rendering until the render
+/// world has actually cooked the op, i.e. once its pipeline has compiled. Bypassed ops that
+/// pass through still render, copying their input instead of running their shader.
+#[derive(Component, Default, Debug)]
+pub struct TextureOpUncooked;
+
+fn cook_op_cameras(
+    mut commands: Commands,
+    cooked: Res<TextureOpCooked>,
+    mut op_q: Query<
+        (
+            Entity,
+            &mut Camera,
+            &OpInputs,
+            Has<Execute>,
+            Has<TextureOpUncooked>,
+        ),
+        With<TextureOpInputImages>,
+    >,
+) {
+    let cooked = std::mem::take(&mut *cooked.lock().unwrap());
+    for (entity, mut camera, inputs, execute, uncooked) in op_q.iter_mut() {
+        // The render world skips ops with unconnected inputs, whose output was cleared when they
+        // were disconnected, so there's nothing to wait for
+        let is_active = if !inputs.is_fully_connected() {
+            if uncooked {
+                commands.entity(entity).remove::<TextureOpUncooked>();
+            }
+            false
+        } else if execute {
+            commands.entity(entity).insert(TextureOpUncooked);
+            true
+        } else if uncooked && cooked.contains(&entity) {
+            commands.entity(entity).remove::<TextureOpUncooked>();
+            false
+        } else {
+            uncooked
+        };
+
+        if camera.is_active != is_active {
+            camera.is_active = is_active;
+        }
+    }
+}
+
 pub fn update_op_cameras(mut op_q: Query<(&mut Camera, &mut OpImage), Changed<OpImage>>) {
     for (mut camera, mut image) in op_q.iter_mut() {
         camera.target = RenderTarget::Image(image.0.clone());
@@ -237,9 +312,6 @@ pub fn update_op_cameras(mut op_q: Query<(&mut Camera, &mut OpImage), Changed<Op
 
 pub trait TextureOp: Op {
     const SHADER: &'static str;
-    type Uniform: Component + ExtractComponent + ShaderType + WriteInto + Clone + Default;
-
-    fn params() -> Vec<ParamBundle>;
-
-    fn update_uniform(uniform: &mut Self::Uniform, params: &Vec<(&ParamName, &ParamValue)>);
+    /// The uniform passed to the shader, which declares the op's params.
+    type Uniform: Component + ExtractComponent + ShaderType + WriteInto + Clone + OpParams;
 }
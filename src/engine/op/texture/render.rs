@@ -1,11 +1,13 @@
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
 
 use bevy::asset::LoadState;
 use bevy::core_pipeline::fullscreen_vertex_shader::fullscreen_shader_vertex_state;
 use bevy::ecs::query::QueryItem;
 use bevy::prelude::*;
 use bevy::render::camera::{CameraOutputMode, ExtractedCamera};
+use bevy::render::diagnostic::RecordDiagnostics;
 use bevy::render::extract_component::{
     ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
     UniformComponentPlugin,
@@ -18,21 +20,23 @@ use bevy::render::render_resource::binding_types::{sampler, texture_2d, uniform_
 use bevy::render::render_resource::encase::internal::WriteInto;
 use bevy::render::render_resource::{
     BindGroup, BindGroupEntry, BindGroupLayout, CachedRenderPipelineId, ColorTargetState,
-    ColorWrites, FragmentState, IntoBinding, LoadOp, MultisampleState, Operations, PipelineCache,
-    PrimitiveState, RenderPassColorAttachment, RenderPassDescriptor, RenderPipelineDescriptor,
-    SamplerBindingType, ShaderStages, ShaderType, SpecializedRenderPipeline,
-    SpecializedRenderPipelines, StoreOp, TextureFormat, TextureSampleType,
+    ColorWrites, Extent3d, FragmentState, IntoBinding, LoadOp, MultisampleState, Operations,
+    PipelineCache, PrimitiveState, RenderPassColorAttachment, RenderPassDescriptor,
+    RenderPipelineDescriptor, SamplerBindingType, ShaderStages, ShaderType,
+    SpecializedRenderPipeline, SpecializedRenderPipelines, StoreOp, TextureFormat,
+    TextureSampleType,
 };
 use bevy::render::renderer::{RenderContext, RenderDevice};
 use bevy::render::texture::{BevyDefault, GpuImage};
 use bevy::render::view::{ExtractedView, ViewTarget};
 use bevy::render::{render_graph, Render, RenderApp, RenderSet};
-use bevy::utils::{info, HashMap};
+use bevy::utils::{info, HashMap, HashSet};
 
+use crate::engine::op::stats::texture_op_span;
 use crate::engine::op::texture::types::composite::TextureOpComposite;
 use crate::engine::op::texture::types::ramp::TextureOpRamp;
 use crate::engine::op::texture::TextureOp;
-use crate::engine::op::{Op, OpInputs, OpType};
+use crate::engine::op::{Op, OpBypass, OpImage, OpInputs, OpType};
 
 #[derive(Default)]
 pub struct TextureOpRenderPlugin<T> {
@@ -86,6 +90,11 @@ where
     }
 }
 
+/// The texture ops that have rendered since the main world last checked. Shared between the
+/// main and render worlds, as an op isn't cooked until its pipeline and inputs are ready.
+#[derive(Resource, Deref, Clone, Default, Debug)]
+pub struct TextureOpCooked(pub Arc<Mutex<HashSet<Entity>>>);
+
 #[derive(Resource, Debug)]
 pub struct TextureOpShaderHandle<T>(pub Handle<Shader>, PhantomData<T>);
 
@@ -159,9 +168,10 @@ pub fn prepare_texture_op_bind_group<T>(
             continue;
         }
 
+        // Bind inputs in port order
         let mut gpu_images = vec![];
-        for (_, image) in op_images.iter() {
-            if let Some(image) = images.get(image) {
+        for port in 0..inputs.count as u8 {
+            if let Some(image) = op_images.get(&port).and_then(|image| images.get(image)) {
                 gpu_images.push(image);
             }
         }
@@ -246,8 +256,9 @@ impl SpecializedRenderPipeline for TextureOpPipeline {
     }
 }
 
+/// The images connected to each input port of a texture op.
 #[derive(Component, ExtractComponent, Deref, DerefMut, Clone, Debug, Default)]
-pub struct TextureOpInputImages(pub HashMap<Entity, Handle<Image>>);
+pub struct TextureOpInputImages(pub HashMap<u8, Handle<Image>>);
 
 #[derive(Component, Debug)]
 pub struct TextureOpPipelineId(pub CachedRenderPipelineId);
@@ -263,14 +274,20 @@ impl render_graph::ViewNode for TextureOpViewNode {
         &'static ViewTarget,
         &'static TextureOpBindGroup,
         &'static TextureOpPipelineId,
+        &'static OpInputs,
+        &'static TextureOpInputImages,
+        &'static OpImage,
+        Has<OpBypass>,
         Option<&'static ExtractedCamera>,
     );
 
     fn run(
         &self,
-        _graph: &mut RenderGraphContext,
+        graph: &mut RenderGraphContext,
         render_context: &mut RenderContext,
-        (view_target, bind_group, pipeline_id, camera): QueryItem<Self::ViewQuery>,
+        (view_target, bind_group, pipeline_id, inputs, input_images, image, bypass, camera): QueryItem<
+            Self::ViewQuery,
+        >,
         world: &World,
     ) -> Result<(), NodeRunError> {
         if let Some(camera) = camera {
@@ -279,12 +296,41 @@ impl render_graph::ViewNode for TextureOpViewNode {
             }
         }
 
+        if bypass && inputs.count == 1 {
+            let images = world.resource::<RenderAssets<GpuImage>>();
+            let input = input_images.get(&0).and_then(|input| images.get(input));
+            let Some((input, output)) = input.zip(images.get(&image.0)) else {
+                return Ok(());
+            };
+
+            // Pass the input through, cropped to our resolution
+            let size = input.size.min(output.size);
+            render_context.command_encoder().copy_texture_to_texture(
+                input.texture.as_image_copy(),
+                output.texture.as_image_copy(),
+                Extent3d {
+                    width: size.x,
+                    height: size.y,
+                    depth_or_array_layers: 1,
+                },
+            );
+
+            world
+                .resource::<TextureOpCooked>()
+                .lock()
+                .unwrap()
+                .insert(graph.view_entity());
+
+            return Ok(());
+        }
+
         let pipeline_cache = world.resource::<PipelineCache>();
         let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline_id.0) else {
             warn!("TextureOpViewNode missing pipeline {:?}", pipeline_id);
             return Ok(());
         };
 
+        let diagnostics = render_context.diagnostic_recorder();
         let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
             label: Some("texture_op_pass"),
             color_attachments: &[Some(RenderPassColorAttachment {
@@ -300,10 +346,21 @@ impl render_graph::ViewNode for TextureOpViewNode {
             occlusion_query_set: None,
         });
 
+        let pass_span =
+            diagnostics.pass_span(&mut render_pass, texture_op_span(graph.view_entity()));
+
         render_pass.set_render_pipeline(pipeline);
         render_pass.set_bind_group(0, &bind_group.0 .0, &[bind_group.0 .1]);
         render_pass.draw(0..3, 0..1);
 
+        pass_span.end(&mut render_pass);
+
+        world
+            .resource::<TextureOpCooked>()
+            .lock()
+            .unwrap()
+            .insert(graph.view_entity());
+
         Ok(())
     }
 }
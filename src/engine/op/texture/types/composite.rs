@@ -14,7 +14,7 @@ use crate::engine::op::{
     Op, OpExecute, OpOnConnect, OpOnDisconnect, OpPlugin, OpShouldExecute, OpSpawn, OpType,
     OpUpdate,
 };
-use crate::engine::param::{ParamBundle, ParamName, ParamOrder, ParamValue};
+use crate::engine::param::{OpParams, ParamBundle};
 
 #[derive(Default)]
 pub struct TextureOpCompositePlugin;
@@ -33,6 +33,8 @@ impl Op for TextureOpComposite {
     const INPUTS: usize = 2;
     const OUTPUTS: usize = 1;
     const CATEGORY: &'static str = CATEGORY;
+    const NAME: &'static str = "composite";
+    const DISPLAY_NAME: &'static str = "Composite";
 
     type OpType = OpType<Self>;
 }
@@ -105,28 +107,6 @@ impl OpOnDisconnect for TextureOpComposite {
 impl TextureOp for TextureOpComposite {
     const SHADER: &'static str = "shaders/texture/composite.wgsl";
     type Uniform = CompositeSettings;
-
-    fn params() -> Vec<ParamBundle> {
-        vec![ParamBundle {
-            name: ParamName("Mode".to_string()),
-            value: ParamValue::U32(0),
-            order: ParamOrder(0),
-            ..default()
-        }]
-    }
-
-    fn update_uniform(uniform: &mut Self::Uniform, params: &Vec<(&ParamName, &ParamValue)>) {
-        for (name, value) in params {
-            match name.as_str() {
-                "Mode" => {
-                    if let ParamValue::U32(value) = value {
-                        uniform.mode = *value;
-                    }
-                }
-                _ => {}
-            }
-        }
-    }
 }
 
 #[derive(Component, ExtractComponent, Clone, Default, Debug)]
@@ -142,6 +122,13 @@ pub enum CompositeMode {
 }
 
 impl CompositeMode {
+    pub const ALL: [CompositeMode; 4] = [
+        CompositeMode::Add,
+        CompositeMode::Multiply,
+        CompositeMode::Subtract,
+        CompositeMode::Divide,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             CompositeMode::Add => "Add",
@@ -167,8 +154,9 @@ impl CompositeMode {
 }
 
 // This is the component that will get passed to the shader
-#[derive(Component, Default, Debug, Clone, Copy, ExtractComponent, ShaderType)]
+#[derive(Component, Default, Debug, Clone, Copy, ExtractComponent, ShaderType, OpParams)]
 pub struct CompositeSettings {
+    #[param(choices = CompositeMode::ALL.map(|mode| (mode.as_str(), mode.as_u32())))]
     pub mode: u32,
     // WebGL2 structs must be 16 byte aligned.
     #[cfg(feature = "webgl2")]
@@ -0,0 +1,134 @@
+use bevy::ecs::system::SystemParamItem;
+use bevy::prelude::*;
+use bevy::render::extract_component::{ExtractComponent, ExtractComponentPlugin};
+use bevy::render::render_resource::ShaderType;
+
+use crate::engine::graph::event::{Connect, Disconnect};
+use crate::engine::op::texture::render::TextureOpRenderPlugin;
+use crate::engine::op::texture::{
+    create_bundle, on_connect, on_disconnect, params, update, DefaultTextureBundle,
+    DefaultTextureOnConnectParam, DefaultTextureOnDisconnectParam, DefaultTextureSpawnParam,
+    DefaultTextureUpdateParam, TextureOp, CATEGORY,
+};
+use crate::engine::op::{
+    Op, OpDelayedInputs, OpExecute, OpOnConnect, OpOnDisconnect, OpPlugin, OpShouldExecute,
+    OpSpawn, OpType, OpUpdate,
+};
+use crate::engine::param::{OpParams, ParamBundle};
+
+#[derive(Default)]
+pub struct TextureOpFeedbackPlugin;
+
+impl Plugin for TextureOpFeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            ExtractComponentPlugin::<OpType<TextureOpFeedback>>::default(),
+            OpPlugin::<TextureOpFeedback>::default(),
+            TextureOpRenderPlugin::<TextureOpFeedback>::default(),
+        ));
+    }
+}
+
+/// Reads the previous frame of its input, which lets it close a loop in the graph.
+#[derive(Component, ExtractComponent, Clone, Default, Debug)]
+pub struct TextureOpFeedback;
+
+impl Op for TextureOpFeedback {
+    const INPUTS: usize = 1;
+    const OUTPUTS: usize = 1;
+    const CATEGORY: &'static str = CATEGORY;
+    const NAME: &'static str = "feedback";
+    const DISPLAY_NAME: &'static str = "Feedback";
+
+    type OpType = OpType<Self>;
+}
+
+impl OpSpawn for TextureOpFeedback {
+    type Param = DefaultTextureSpawnParam;
+    type Bundle = (DefaultTextureBundle<Self>, OpDelayedInputs);
+
+    fn params(bundle: &Self::Bundle) -> Vec<ParamBundle> {
+        params::<Self>(&bundle.0)
+    }
+
+    fn create_bundle<'w>(
+        entity: Entity,
+        param: &mut SystemParamItem<'w, '_, Self::Param>,
+    ) -> Self::Bundle {
+        let mut bundle = create_bundle::<Self>(entity, param);
+        // Render before the other texture ops, so our input still holds last frame's image
+        bundle.0.camera.camera.order = 2;
+        (bundle, OpDelayedInputs)
+    }
+}
+
+impl OpUpdate for TextureOpFeedback {
+    type Param = DefaultTextureUpdateParam<Self>;
+
+    fn update<'w>(entity: Entity, param: &mut SystemParamItem<'w, '_, Self::Param>) {
+        update::<Self>(entity, param)
+    }
+}
+
+impl OpShouldExecute for TextureOpFeedback {
+    type Param = ();
+
+    fn should_execute<'w>(
+        entity: Entity,
+        param: &mut SystemParamItem<'w, '_, Self::Param>,
+    ) -> bool {
+        // Our input changes every frame
+        true
+    }
+}
+
+impl OpExecute for TextureOpFeedback {
+    fn execute(&self, entity: Entity, world: &mut World) {}
+}
+
+impl OpOnConnect for TextureOpFeedback {
+    type Param = DefaultTextureOnConnectParam;
+
+    fn on_connect<'w>(
+        entity: Entity,
+        event: Connect,
+        fully_connected: bool,
+        param: &mut SystemParamItem<'w, '_, Self::Param>,
+    ) {
+        on_connect(entity, event, fully_connected, param)
+    }
+}
+
+impl OpOnDisconnect for TextureOpFeedback {
+    type Param = DefaultTextureOnDisconnectParam;
+
+    fn on_disconnect<'w>(
+        entity: Entity,
+        event: Disconnect,
+        fully_connected: bool,
+        param: &mut SystemParamItem<'w, '_, Self::Param>,
+    ) {
+        on_disconnect(entity, event, fully_connected, param)
+    }
+}
+
+impl TextureOp for TextureOpFeedback {
+    const SHADER: &'static str = "shaders/texture/feedback.wgsl";
+    type Uniform = TextureFeedbackSettings;
+}
+
+// This is the component that will get passed to the shader
+#[derive(Component, Default, Debug, Clone, Copy, ExtractComponent, ShaderType, OpParams)]
+pub struct TextureFeedbackSettings {
+    #[param(
+        default = 0.95,
+        min = 0.0,
+        max = 1.0,
+        step = 0.01,
+        tooltip = "How much of the previous frame is kept"
+    )]
+    pub decay: f32,
+    // WebGL2 structs must be 16 byte aligned.
+    #[cfg(feature = "webgl2")]
+    _webgl2_padding: Vec3,
+}
@@ -0,0 +1,120 @@
+use bevy::ecs::system::SystemParamItem;
+use bevy::prelude::*;
+use bevy::render::extract_component::{ExtractComponent, ExtractComponentPlugin};
+use bevy::render::render_resource::ShaderType;
+
+use crate::engine::graph::event::{Connect, Disconnect};
+use crate::engine::op::container::ContainerInput;
+use crate::engine::op::texture::render::TextureOpRenderPlugin;
+use crate::engine::op::texture::{
+    create_bundle, on_connect, on_disconnect, params, update, DefaultTextureBundle,
+    DefaultTextureOnConnectParam, DefaultTextureOnDisconnectParam, DefaultTextureSpawnParam,
+    DefaultTextureUpdateParam, TextureOp, CATEGORY,
+};
+use crate::engine::op::{
+    Op, OpExecute, OpOnConnect, OpOnDisconnect, OpPlugin, OpShouldExecute, OpSpawn, OpType,
+    OpUpdate,
+};
+use crate::engine::param::{OpParams, ParamBundle};
+
+#[derive(Default)]
+pub struct TextureOpInPlugin;
+
+impl Plugin for TextureOpInPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            ExtractComponentPlugin::<OpType<TextureOpIn>>::default(),
+            OpPlugin::<TextureOpIn>::default(),
+            TextureOpRenderPlugin::<TextureOpIn>::default(),
+        ));
+    }
+}
+
+/// Outputs whatever is connected to its port on the container it's in. The input is connected
+/// by the container rather than the user.
+#[derive(Component, ExtractComponent, Clone, Default, Debug)]
+pub struct TextureOpIn;
+
+impl Op for TextureOpIn {
+    const INPUTS: usize = 1;
+    const OUTPUTS: usize = 1;
+    const CATEGORY: &'static str = CATEGORY;
+    const NAME: &'static str = "in";
+    const DISPLAY_NAME: &'static str = "In";
+
+    type OpType = OpType<Self>;
+}
+
+impl OpSpawn for TextureOpIn {
+    type Param = DefaultTextureSpawnParam;
+    type Bundle = (DefaultTextureBundle<Self>, ContainerInput);
+
+    fn params(bundle: &Self::Bundle) -> Vec<ParamBundle> {
+        params::<Self>(&bundle.0)
+    }
+
+    fn create_bundle<'w>(
+        entity: Entity,
+        param: &mut SystemParamItem<'w, '_, Self::Param>,
+    ) -> Self::Bundle {
+        (create_bundle::<Self>(entity, param), ContainerInput)
+    }
+}
+
+impl OpUpdate for TextureOpIn {
+    type Param = DefaultTextureUpdateParam<Self>;
+
+    fn update<'w>(entity: Entity, param: &mut SystemParamItem<'w, '_, Self::Param>) {
+        update::<Self>(entity, param)
+    }
+}
+
+impl OpShouldExecute for TextureOpIn {
+    type Param = ();
+}
+
+impl OpExecute for TextureOpIn {
+    fn execute(&self, entity: Entity, world: &mut World) {}
+}
+
+impl OpOnConnect for TextureOpIn {
+    type Param = DefaultTextureOnConnectParam;
+
+    fn on_connect<'w>(
+        entity: Entity,
+        event: Connect,
+        fully_connected: bool,
+        param: &mut SystemParamItem<'w, '_, Self::Param>,
+    ) {
+        on_connect(entity, event, fully_connected, param)
+    }
+}
+
+impl OpOnDisconnect for TextureOpIn {
+    type Param = DefaultTextureOnDisconnectParam;
+
+    fn on_disconnect<'w>(
+        entity: Entity,
+        event: Disconnect,
+        fully_connected: bool,
+        param: &mut SystemParamItem<'w, '_, Self::Param>,
+    ) {
+        on_disconnect(entity, event, fully_connected, param)
+    }
+}
+
+impl TextureOp for TextureOpIn {
+    const SHADER: &'static str = "shaders/texture/passthrough.wgsl";
+    type Uniform = TextureInSettings;
+}
+
+// This is the component that will get passed to the shader
+#[derive(Component, Default, Debug, Clone, Copy, ExtractComponent, ShaderType, OpParams)]
+pub struct TextureInSettings {
+    /// Ops are assigned to their container's ports in order of this.
+    #[param]
+    pub port: u32,
+    // WebGL2 structs must be 16 byte aligned.
+    #[cfg(feature = "webgl2")]
+    _webgl2_padding: Vec3,
+}
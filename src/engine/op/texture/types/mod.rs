@@ -1,4 +1,7 @@
 pub mod composite;
+pub mod feedback;
+pub mod input;
 pub mod noise;
+pub mod output;
 pub mod ramp;
 pub mod render;
@@ -13,7 +13,7 @@ use crate::engine::op::{
     Op, OpExecute, OpInputs, OpOnConnect, OpOnDisconnect, OpPlugin, OpShouldExecute, OpSpawn,
     OpType, OpUpdate,
 };
-use crate::engine::param::{ParamBundle, ParamName, ParamOrder, ParamValue};
+use crate::engine::param::{OpParams, ParamBundle};
 
 #[derive(Default)]
 pub struct TextureOpNoisePlugin;
@@ -35,6 +35,8 @@ impl Op for TextureOpNoise {
     const INPUTS: usize = 0;
     const OUTPUTS: usize = 1;
     const CATEGORY: &'static str = CATEGORY;
+    const NAME: &'static str = "noise";
+    const DISPLAY_NAME: &'static str = "Noise";
 
     type OpType = OpType<Self>;
 }
@@ -106,71 +108,18 @@ impl OpOnDisconnect for TextureOpNoise {
 impl TextureOp for TextureOpNoise {
     const SHADER: &'static str = "shaders/texture/noise.wgsl";
     type Uniform = TextureNoiseSettings;
-
-    fn params() -> Vec<ParamBundle> {
-        vec![
-            ParamBundle {
-                name: ParamName("Strength".to_string()),
-                value: ParamValue::F32(10.0),
-                order: ParamOrder(0),
-                ..default()
-            },
-            ParamBundle {
-                name: ParamName("B".to_string()),
-                value: ParamValue::F32(10.0),
-                order: ParamOrder(0),
-                ..default()
-            },
-            ParamBundle {
-                name: ParamName("C".to_string()),
-                value: ParamValue::F32(10.0),
-                order: ParamOrder(0),
-                ..default()
-            },
-            ParamBundle {
-                name: ParamName("Seed".to_string()),
-                value: ParamValue::F32(10.0),
-                order: ParamOrder(0),
-                ..default()
-            },
-        ]
-    }
-
-    fn update_uniform(uniform: &mut Self::Uniform, params: &Vec<(&ParamName, &ParamValue)>) {
-        for (name, value) in params {
-            match name.as_str() {
-                "Strength" => {
-                    if let ParamValue::F32(value) = value {
-                        uniform.strength = *value;
-                    }
-                }
-                "B" => {
-                    if let ParamValue::F32(value) = value {
-                        uniform.b = *value;
-                    }
-                }
-                "C" => {
-                    if let ParamValue::F32(value) = value {
-                        uniform.c = *value;
-                    }
-                }
-                "Seed" => {
-                    if let ParamValue::F32(value) = value {
-                        uniform.seed = *value;
-                    }
-                }
-                _ => {}
-            }
-        }
-    }
 }
 
 // This is the component that will get passed to the shader
-#[derive(Component, Default, Debug, Clone, Copy, ExtractComponent, ShaderType)]
+#[derive(Component, Default, Debug, Clone, Copy, ExtractComponent, ShaderType, OpParams)]
 pub struct TextureNoiseSettings {
+    #[param(default = 10.0, soft_min = 0.0, soft_max = 100.0)]
     pub strength: f32,
+    #[param(default = 10.0)]
     pub b: f32,
+    #[param(default = 10.0)]
     pub c: f32,
+    #[param(default = 10.0, soft_min = 0.0, soft_max = 100.0, step = 1.0)]
     pub seed: f32,
     #[cfg(feature = "webgl2")]
     _webgl2_padding: Vec3,
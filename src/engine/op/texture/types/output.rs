@@ -0,0 +1,119 @@
+use bevy::ecs::system::SystemParamItem;
+use bevy::prelude::*;
+use bevy::render::extract_component::{ExtractComponent, ExtractComponentPlugin};
+use bevy::render::render_resource::ShaderType;
+
+use crate::engine::graph::event::{Connect, Disconnect};
+use crate::engine::op::container::ContainerOutput;
+use crate::engine::op::texture::render::TextureOpRenderPlugin;
+use crate::engine::op::texture::{
+    create_bundle, on_connect, on_disconnect, params, update, DefaultTextureBundle,
+    DefaultTextureOnConnectParam, DefaultTextureOnDisconnectParam, DefaultTextureSpawnParam,
+    DefaultTextureUpdateParam, TextureOp, CATEGORY,
+};
+use crate::engine::op::{
+    Op, OpExecute, OpOnConnect, OpOnDisconnect, OpPlugin, OpShouldExecute, OpSpawn, OpType,
+    OpUpdate,
+};
+use crate::engine::param::{OpParams, ParamBundle};
+
+#[derive(Default)]
+pub struct TextureOpOutPlugin;
+
+impl Plugin for TextureOpOutPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            ExtractComponentPlugin::<OpType<TextureOpOut>>::default(),
+            OpPlugin::<TextureOpOut>::default(),
+            TextureOpRenderPlugin::<TextureOpOut>::default(),
+        ));
+    }
+}
+
+/// Outputs its input from its port on the container it's in.
+#[derive(Component, ExtractComponent, Clone, Default, Debug)]
+pub struct TextureOpOut;
+
+impl Op for TextureOpOut {
+    const INPUTS: usize = 1;
+    const OUTPUTS: usize = 0;
+    const CATEGORY: &'static str = CATEGORY;
+    const NAME: &'static str = "out";
+    const DISPLAY_NAME: &'static str = "Out";
+
+    type OpType = OpType<Self>;
+}
+
+impl OpSpawn for TextureOpOut {
+    type Param = DefaultTextureSpawnParam;
+    type Bundle = (DefaultTextureBundle<Self>, ContainerOutput);
+
+    fn params(bundle: &Self::Bundle) -> Vec<ParamBundle> {
+        params::<Self>(&bundle.0)
+    }
+
+    fn create_bundle<'w>(
+        entity: Entity,
+        param: &mut SystemParamItem<'w, '_, Self::Param>,
+    ) -> Self::Bundle {
+        (create_bundle::<Self>(entity, param), ContainerOutput)
+    }
+}
+
+impl OpUpdate for TextureOpOut {
+    type Param = DefaultTextureUpdateParam<Self>;
+
+    fn update<'w>(entity: Entity, param: &mut SystemParamItem<'w, '_, Self::Param>) {
+        update::<Self>(entity, param)
+    }
+}
+
+impl OpShouldExecute for TextureOpOut {
+    type Param = ();
+}
+
+impl OpExecute for TextureOpOut {
+    fn execute(&self, entity: Entity, world: &mut World) {}
+}
+
+impl OpOnConnect for TextureOpOut {
+    type Param = DefaultTextureOnConnectParam;
+
+    fn on_connect<'w>(
+        entity: Entity,
+        event: Connect,
+        fully_connected: bool,
+        param: &mut SystemParamItem<'w, '_, Self::Param>,
+    ) {
+        on_connect(entity, event, fully_connected, param)
+    }
+}
+
+impl OpOnDisconnect for TextureOpOut {
+    type Param = DefaultTextureOnDisconnectParam;
+
+    fn on_disconnect<'w>(
+        entity: Entity,
+        event: Disconnect,
+        fully_connected: bool,
+        param: &mut SystemParamItem<'w, '_, Self::Param>,
+    ) {
+        on_disconnect(entity, event, fully_connected, param)
+    }
+}
+
+impl TextureOp for TextureOpOut {
+    const SHADER: &'static str = "shaders/texture/passthrough.wgsl";
+    type Uniform = TextureOutSettings;
+}
+
+// This is the component that will get passed to the shader
+#[derive(Component, Default, Debug, Clone, Copy, ExtractComponent, ShaderType, OpParams)]
+pub struct TextureOutSettings {
+    /// Ops are assigned to their container's ports in order of this.
+    #[param]
+    pub port: u32,
+    // WebGL2 structs must be 16 byte aligned.
+    #[cfg(feature = "webgl2")]
+    _webgl2_padding: Vec3,
+}
@@ -13,7 +13,7 @@ use crate::engine::op::{
     Op, OpExecute, OpOnConnect, OpOnDisconnect, OpPlugin, OpShouldExecute, OpSpawn, OpType,
     OpUpdate,
 };
-use crate::engine::param::{ParamBundle, ParamName, ParamOrder, ParamValue};
+use crate::engine::param::{OpParams, ParamBundle};
 
 #[derive(Default)]
 pub struct TextureOpRampPlugin;
@@ -35,6 +35,8 @@ impl Op for TextureOpRamp {
     const INPUTS: usize = 0;
     const OUTPUTS: usize = 1;
     const CATEGORY: &'static str = CATEGORY;
+    const NAME: &'static str = "ramp";
+    const DISPLAY_NAME: &'static str = "Ramp";
 
     type OpType = OpType<Self>;
 }
@@ -106,52 +108,6 @@ impl OpOnDisconnect for TextureOpRamp {
 impl TextureOp for TextureOpRamp {
     const SHADER: &'static str = "shaders/texture/ramp.wgsl";
     type Uniform = TextureRampSettings;
-
-    fn params() -> Vec<ParamBundle> {
-        vec![
-            ParamBundle {
-                name: ParamName("Color A".to_string()),
-                value: ParamValue::Color(Vec4::new(1.0, 0.0, 0.0, 1.0)),
-                order: ParamOrder(0),
-                ..default()
-            },
-            ParamBundle {
-                name: ParamName("Color B".to_string()),
-                value: ParamValue::Color(Vec4::new(0.0, 0.0, 1.0, 1.0)),
-                order: ParamOrder(1),
-                ..default()
-            },
-            ParamBundle {
-                name: ParamName("Mode".to_string()),
-                value: ParamValue::U32(0),
-                order: ParamOrder(1),
-                ..default()
-            },
-        ]
-    }
-
-    fn update_uniform(uniform: &mut Self::Uniform, params: &Vec<(&ParamName, &ParamValue)>) {
-        for (name, value) in params {
-            match name.as_str() {
-                "Color A" => {
-                    if let ParamValue::Color(color) = value {
-                        uniform.color_a = *color;
-                    }
-                }
-                "Color B" => {
-                    if let ParamValue::Color(color) = value {
-                        uniform.color_b = *color;
-                    }
-                }
-                "Mode" => {
-                    if let ParamValue::U32(mode) = value {
-                        uniform.mode = *mode;
-                    }
-                }
-                _ => {}
-            }
-        }
-    }
 }
 
 #[derive(Default, Clone, Copy, Debug, PartialEq)]
@@ -163,6 +119,12 @@ pub enum TextureRampMode {
 }
 
 impl TextureRampMode {
+    pub const ALL: [TextureRampMode; 3] = [
+        TextureRampMode::Horizontal,
+        TextureRampMode::Vertical,
+        TextureRampMode::Circular,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             TextureRampMode::Horizontal => "Horizontal",
@@ -186,10 +148,13 @@ impl TextureRampMode {
 }
 
 // This is the component that will get passed to the shader
-#[derive(Component, Default, Debug, Clone, Copy, ExtractComponent, ShaderType)]
+#[derive(Component, Default, Debug, Clone, Copy, ExtractComponent, ShaderType, OpParams)]
 pub struct TextureRampSettings {
+    #[param(color, default = Vec4::new(1.0, 0.0, 0.0, 1.0))]
     pub color_a: Vec4,
+    #[param(color, default = Vec4::new(0.0, 0.0, 1.0, 1.0))]
     pub color_b: Vec4,
+    #[param(choices = TextureRampMode::ALL.map(|mode| (mode.as_str(), mode.as_u32())))]
     pub mode: u32,
     // WebGL2 structs must be 16 byte aligned.
     #[cfg(feature = "webgl2")]
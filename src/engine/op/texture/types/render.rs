@@ -95,5 +95,7 @@ impl Op for TextureOpRender {
     const INPUTS: usize = 0;
     const OUTPUTS: usize = 1;
     const CATEGORY: &'static str = "Texture";
+    const NAME: &'static str = "render";
+    const DISPLAY_NAME: &'static str = "Render";
     type OpType = OpType<Self>;
 }
@@ -2,21 +2,43 @@ use bevy::ecs::system::SystemParam;
 use std::collections::BTreeMap;
 use std::hash::{Hash, Hasher};
 use std::ops::DerefMut;
+use std::path::{Path, PathBuf};
 
 use bevy::prelude::*;
-use bevy::utils::AHasher;
+use bevy::utils::{AHasher, HashSet};
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+use serde::{Deserialize, Serialize};
 
-use crate::engine::op::{OpCategory, OpRef};
+use crate::engine::animation::Keyframes;
+use crate::engine::graph::{GraphId, GraphState, LINK_PORT};
+use crate::engine::op::component::types::camera::ComponentOpCamera;
+use crate::engine::op::component::types::light::ComponentOpLight;
+use crate::engine::op::{OpCategory, OpDespawn, OpName, OpRef, OpType, OpTypeName};
 use crate::engine::script::update;
-use crate::index::{CompositeIndex2, CompositeIndex2Plugin};
+use crate::index::{CompositeIndex2, CompositeIndex2Plugin, UniqueIndex};
 use crate::Sets;
 
+pub use sepiascraped_derive::OpParams;
+
 pub struct ParamPlugin;
 
 impl Plugin for ParamPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, validate.in_set(Sets::Params))
-            .add_plugins(CompositeIndex2Plugin::<OpRef, ParamName>::new());
+        app.add_systems(
+            Update,
+            (
+                follow_links,
+                update_link_edges,
+                clear_dangling_refs,
+                apply_overrides,
+                update_provenance,
+                validate,
+            )
+                .chain()
+                .in_set(Sets::Params),
+        )
+        .add_systems(Last, reset_pulses)
+        .add_plugins(CompositeIndex2Plugin::<OpRef, ParamName>::new());
     }
 }
 
@@ -26,10 +48,161 @@ pub struct ParamBundle {
     pub value: ParamValue,
     pub order: ParamOrder,
     pub page: ParamPage,
+    pub meta: ParamMeta,
 }
 
+/// The inspector tab a param is shown on. Params on the default, empty page are shown first.
 #[derive(Component, Clone, Default, Debug)]
 pub struct ParamPage(pub String);
+
+/// Describes the values a param accepts and how it's edited. The param's default is the value
+/// it's spawned with, see [ParamDefault].
+#[derive(Component, Clone, Default, Debug)]
+pub struct ParamMeta {
+    /// The lowest value the param accepts.
+    pub min: Option<f32>,
+    /// The highest value the param accepts.
+    pub max: Option<f32>,
+    /// The range shown by a slider, which can be exceeded by typing in a value.
+    pub soft_min: Option<f32>,
+    pub soft_max: Option<f32>,
+    /// The increment the value is snapped to when dragged.
+    pub step: Option<f64>,
+    /// The unit shown after the value, i.e. `px`.
+    pub unit: Option<String>,
+    pub tooltip: Option<String>,
+    /// Labeled values for params that select from a menu, i.e. a blend mode.
+    pub choices: Vec<(String, u32)>,
+    /// A collapsible section of the param's page that the param is grouped under.
+    pub section: Option<String>,
+}
+
+impl ParamMeta {
+    pub fn range(mut self, min: f32, max: f32) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    pub fn soft_range(mut self, min: f32, max: f32) -> Self {
+        self.soft_min = Some(min);
+        self.soft_max = Some(max);
+        self
+    }
+
+    pub fn step(mut self, step: f64) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    pub fn unit(mut self, unit: impl Into<String>) -> Self {
+        self.unit = Some(unit.into());
+        self
+    }
+
+    pub fn tooltip(mut self, tooltip: impl Into<String>) -> Self {
+        self.tooltip = Some(tooltip.into());
+        self
+    }
+
+    pub fn choices<'a>(mut self, choices: impl IntoIterator<Item = (&'a str, u32)>) -> Self {
+        self.choices = choices
+            .into_iter()
+            .map(|(label, value)| (label.to_string(), value))
+            .collect();
+        self
+    }
+
+    pub fn section(mut self, section: impl Into<String>) -> Self {
+        self.section = Some(section.into());
+        self
+    }
+
+    /// The label of a choice.
+    pub fn choice_label(&self, value: u32) -> Option<&str> {
+        self.choices
+            .iter()
+            .find(|(_, choice)| *choice == value)
+            .map(|(label, _)| label.as_str())
+    }
+
+    /// Check a value against the param's range and choices.
+    pub fn validate(&self, value: &ParamValue) -> Result<(), ParamError> {
+        if let ParamValue::U32(value) = value {
+            if !self.choices.is_empty() && self.choice_label(*value).is_none() {
+                return Err(ParamError::InvalidChoice(*value));
+            }
+        }
+
+        let out_of_range =
+            |x: f32| self.min.is_some_and(|min| x < min) || self.max.is_some_and(|max| x > max);
+        let components = match value {
+            ParamValue::F32(x) => vec![*x],
+            ParamValue::U32(x) => vec![*x as f32],
+            ParamValue::I32(x) => vec![*x as f32],
+            ParamValue::Vec2(v) => v.to_array().to_vec(),
+            ParamValue::Vec3(v) => v.to_array().to_vec(),
+            ParamValue::Vec4(v) => v.to_array().to_vec(),
+            ParamValue::UVec2(v) => vec![v.x as f32, v.y as f32],
+            _ => vec![],
+        };
+        match components.into_iter().find(|x| out_of_range(*x)) {
+            Some(value) => Err(ParamError::OutOfRange {
+                value,
+                min: self.min.unwrap_or(f32::MIN),
+                max: self.max.unwrap_or(f32::MAX),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Clamp a value to the param's range. Returns true if the value changed.
+    pub fn clamp(&self, value: &mut ParamValue) -> bool {
+        if self.min.is_none() && self.max.is_none() {
+            return false;
+        }
+
+        let min = self.min.unwrap_or(f32::MIN);
+        let max = self.max.unwrap_or(f32::MAX);
+        let clamped = match value {
+            ParamValue::F32(x) => ParamValue::F32(x.clamp(min, max)),
+            ParamValue::U32(x) => ParamValue::U32((*x as f32).clamp(min, max) as u32),
+            ParamValue::I32(x) => ParamValue::I32((*x as f32).clamp(min, max) as i32),
+            ParamValue::Vec2(v) => ParamValue::Vec2(v.clamp(Vec2::splat(min), Vec2::splat(max))),
+            ParamValue::Vec3(v) => ParamValue::Vec3(v.clamp(Vec3::splat(min), Vec3::splat(max))),
+            ParamValue::Vec4(v) => ParamValue::Vec4(v.clamp(Vec4::splat(min), Vec4::splat(max))),
+            ParamValue::UVec2(v) => ParamValue::UVec2(UVec2::new(
+                (v.x as f32).clamp(min, max) as u32,
+                (v.y as f32).clamp(min, max) as u32,
+            )),
+            _ => return false,
+        };
+        if clamped == *value {
+            return false;
+        }
+
+        *value = clamped;
+        true
+    }
+}
+
+/// Pulses are only triggered for a single frame.
+fn reset_pulses(mut params_q: Query<&mut ParamValue>) {
+    for mut param in params_q.iter_mut() {
+        if param.is_triggered() {
+            *param = ParamValue::Pulse(false);
+        }
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, thiserror::Error)]
+pub enum ParamError {
+    #[error("{value} is outside of {min}..={max}")]
+    OutOfRange { value: f32, min: f32, max: f32 },
+    #[error("{0} is not one of the param's choices")]
+    InvalidChoice(u32),
+}
 #[derive(Component, Clone, Default, Debug, Eq, Ord, PartialOrd, PartialEq)]
 pub struct ParamOrder(pub u32);
 #[derive(Component, Clone, Default, Debug)]
@@ -39,9 +212,72 @@ pub struct Param;
 )]
 pub struct ParamName(pub String);
 
-trait ParamType: Default {}
+/// A rust type that is stored as a [ParamValue].
+pub trait ParamType: Sized {
+    fn into_param(self) -> ParamValue;
+
+    /// Returns `None` if the value is a different type.
+    fn from_param(value: &ParamValue) -> Option<Self>;
+}
+
+macro_rules! impl_param_type {
+    ($ty:ty, $variant:ident) => {
+        impl ParamType for $ty {
+            fn into_param(self) -> ParamValue {
+                ParamValue::$variant(self)
+            }
+
+            fn from_param(value: &ParamValue) -> Option<Self> {
+                match value {
+                    ParamValue::$variant(value) => Some(value.clone()),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+impl_param_type!(f32, F32);
+impl_param_type!(u32, U32);
+impl_param_type!(i32, I32);
+impl_param_type!(bool, Bool);
+impl_param_type!(UVec2, UVec2);
+impl_param_type!(Vec2, Vec2);
+impl_param_type!(Vec3, Vec3);
+impl_param_type!(Quat, Quat);
+impl_param_type!(Mat4, Mat4);
+impl_param_type!(String, String);
+impl_param_type!(PathBuf, Path);
+
+/// Colors are stored as a [Vec4] too.
+impl ParamType for Vec4 {
+    fn into_param(self) -> ParamValue {
+        ParamValue::Vec4(self)
+    }
+
+    fn from_param(value: &ParamValue) -> Option<Self> {
+        match value {
+            ParamValue::Vec4(value) | ParamValue::Color(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/// The params of an op, declared as a struct with `#[derive(OpParams)]`. Each field annotated
+/// with `#[param]` is a param, see the derive macro for its arguments.
+pub trait OpParams: Default {
+    /// The params to spawn for the op.
+    fn params() -> Vec<ParamBundle>;
+
+    /// Set the fields from the op's params. Params that are missing or of a different type are
+    /// left as they are.
+    fn apply(&mut self, params: &[(&ParamName, &ParamValue)]);
 
-#[derive(Component, PartialEq, Clone, Debug, Default)]
+    /// Read the fields from the params of an op.
+    fn read(params: &Params, entity: Entity) -> Self;
+}
+
+#[derive(Component, PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
 pub enum ParamValue {
     #[default]
     None,
@@ -53,6 +289,14 @@ pub enum ParamValue {
     Quat(Quat),
     Color(Vec4),
     Bool(bool),
+    I32(i32),
+    Vec4(Vec4),
+    Mat4(Mat4),
+    String(String),
+    /// A file path, relative to the assets directory.
+    Path(PathBuf),
+    /// A trigger, i.e. a reset button. It's true for the frame it's triggered in.
+    Pulse(bool),
     TextureOp(Option<Entity>),
     MeshOp(Option<Entity>),
     MaterialOp(Option<Entity>),
@@ -60,81 +304,122 @@ pub enum ParamValue {
     LightOps(Vec<Entity>),
 }
 
+/// Accessors for the value of a param, which return `None` if the param is a different type.
 impl ParamValue {
-    pub fn as_f32(&self) -> f32 {
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            ParamValue::F32(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        match self {
+            ParamValue::U32(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        match self {
+            ParamValue::I32(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_uvec2(&self) -> Option<UVec2> {
+        match self {
+            ParamValue::UVec2(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_vec2(&self) -> Option<Vec2> {
+        match self {
+            ParamValue::Vec2(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_vec3(&self) -> Option<Vec3> {
         match self {
-            ParamValue::F32(v) => *v,
-            _ => panic!("Param is not an f32"),
+            ParamValue::Vec3(v) => Some(*v),
+            _ => None,
         }
     }
 
-    pub fn as_u32(&self) -> u32 {
+    pub fn as_vec4(&self) -> Option<Vec4> {
         match self {
-            ParamValue::U32(v) => *v,
-            _ => panic!("Param is not a u32"),
+            ParamValue::Vec4(v) => Some(*v),
+            _ => None,
         }
     }
 
-    pub fn as_uvec2(&self) -> UVec2 {
+    pub fn as_mat4(&self) -> Option<Mat4> {
         match self {
-            ParamValue::UVec2(v) => *v,
-            _ => panic!("Param is not a UVec2"),
+            ParamValue::Mat4(v) => Some(*v),
+            _ => None,
         }
     }
 
-    pub fn as_vec2(&self) -> Vec2 {
+    pub fn as_quat(&self) -> Option<Quat> {
         match self {
-            ParamValue::Vec2(v) => *v,
-            _ => panic!("Param is not a Vec2"),
+            ParamValue::Quat(v) => Some(*v),
+            _ => None,
         }
     }
 
-    pub fn as_vec3(&self) -> Vec3 {
+    pub fn as_color(&self) -> Option<Vec4> {
         match self {
-            ParamValue::Vec3(v) => *v,
-            _ => panic!("Param is not a Vec3"),
+            ParamValue::Color(v) => Some(*v),
+            _ => None,
         }
     }
 
-    pub fn as_quat(&self) -> Quat {
+    pub fn as_bool(&self) -> Option<bool> {
         match self {
-            ParamValue::Quat(v) => *v,
-            _ => panic!("Param is not a Quat"),
+            ParamValue::Bool(v) => Some(*v),
+            _ => None,
         }
     }
 
-    pub fn as_color(&self) -> Vec4 {
+    pub fn as_str(&self) -> Option<&str> {
         match self {
-            ParamValue::Color(v) => *v,
-            _ => panic!("Param is not a Color"),
+            ParamValue::String(v) => Some(v),
+            _ => None,
         }
     }
 
-    pub fn as_bool(&self) -> bool {
+    pub fn as_path(&self) -> Option<&Path> {
         match self {
-            ParamValue::Bool(v) => *v,
-            _ => panic!("Param is not a bool"),
+            ParamValue::Path(v) => Some(v),
+            _ => None,
         }
     }
 
+    /// Whether a pulse was triggered this frame.
+    pub fn is_triggered(&self) -> bool {
+        matches!(self, ParamValue::Pulse(true))
+    }
+
     pub fn as_texture_op(&self) -> Option<Entity> {
         match self {
-            ParamValue::TextureOp(v) => v.clone(),
-            _ => panic!("Param is not a TextureOp"),
+            ParamValue::TextureOp(v) => *v,
+            _ => None,
         }
     }
 
     pub fn as_mesh_op(&self) -> Option<Entity> {
         match self {
-            ParamValue::MeshOp(v) => v.clone(),
-            _ => panic!("Param is not a MeshOp"),
+            ParamValue::MeshOp(v) => *v,
+            _ => None,
         }
     }
 
     pub fn as_material_op(&self) -> Option<Entity> {
         match self {
-            ParamValue::MaterialOp(v) => v.clone(),
-            _ => panic!("Param is not a MaterialOp"),
+            ParamValue::MaterialOp(v) => *v,
+            _ => None,
         }
     }
 }
@@ -168,6 +453,21 @@ impl Hash for ParamValue {
                 v.w.to_bits().hash(state);
             }
             ParamValue::Bool(v) => v.hash(state),
+            ParamValue::I32(v) => v.hash(state),
+            ParamValue::Vec4(v) => {
+                v.x.to_bits().hash(state);
+                v.y.to_bits().hash(state);
+                v.z.to_bits().hash(state);
+                v.w.to_bits().hash(state);
+            }
+            ParamValue::Mat4(v) => {
+                for x in v.to_cols_array() {
+                    x.to_bits().hash(state);
+                }
+            }
+            ParamValue::String(v) => v.hash(state),
+            ParamValue::Path(v) => v.hash(state),
+            ParamValue::Pulse(v) => v.hash(state),
             ParamValue::TextureOp(v) => v.hash(state),
             ParamValue::MeshOp(v) => v.hash(state),
             ParamValue::MaterialOp(v) => v.hash(state),
@@ -180,38 +480,380 @@ impl Hash for ParamValue {
 #[derive(Component, Deref, DerefMut, Default, Debug)]
 pub struct ParamHash(pub u64);
 
+/// The value a param was spawned with.
+#[derive(Component, Deref, DerefMut, Clone, Default, Debug)]
+pub struct ParamDefault(pub ParamValue);
+
+/// Where a param's value can come from, from the lowest priority to the highest. Each frame the
+/// highest layer driving a param sets its value, and the layers below it are ignored.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ParamLayer {
+    /// The value the param was spawned with.
+    #[default]
+    Default,
+    /// A value loaded from the project.
+    Saved,
+    /// A value set in the editor.
+    Ui,
+    /// Keyframes on the timeline.
+    Animation,
+    /// Another op's param, see [ParamLink].
+    Link,
+    /// An expression evaluated every frame, see [ParamExpression].
+    Expression,
+    /// `param!` in a script.
+    Script,
+    /// A value held from the editor while performing, see [ParamOverride].
+    Override,
+}
+
+impl ParamLayer {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ParamLayer::Default => "Default",
+            ParamLayer::Saved => "Saved",
+            ParamLayer::Ui => "UI",
+            ParamLayer::Animation => "Animation",
+            ParamLayer::Link => "Link",
+            ParamLayer::Expression => "Expression",
+            ParamLayer::Script => "Script",
+            ParamLayer::Override => "Override",
+        }
+    }
+
+    /// Whether the layer sets the value every frame, rather than it being set once.
+    pub fn is_driven(&self) -> bool {
+        *self >= ParamLayer::Animation
+    }
+
+    /// The highest layer driving a param, if any. Overrides aren't included, since they're
+    /// applied after every other layer.
+    pub fn driver(
+        scripted: bool,
+        expression: Option<&ParamExpression>,
+        linked: bool,
+        animated: bool,
+    ) -> Option<ParamLayer> {
+        if scripted {
+            Some(ParamLayer::Script)
+        } else if expression.is_some_and(ParamExpression::is_active) {
+            Some(ParamLayer::Expression)
+        } else if linked {
+            Some(ParamLayer::Link)
+        } else if animated {
+            Some(ParamLayer::Animation)
+        } else {
+            None
+        }
+    }
+}
+
+/// Which layers a param's value comes from.
+#[derive(Component, Clone, Copy, Default, Debug, PartialEq)]
+pub struct ParamProvenance {
+    /// The layer that last set the value directly, i.e. [ParamLayer::Saved] once loaded.
+    pub base: ParamLayer,
+    /// The highest layer driving the param, or the base layer when nothing drives it.
+    pub active: ParamLayer,
+}
+
+/// Holds a param at a value set from the editor, above every other layer, until it's removed.
+/// Lets a performer take over a driven param and then release it back to its driver.
+#[derive(Component, Clone, Debug)]
+pub struct ParamOverride(pub ParamValue);
+
+/// A Steel expression evaluated every frame to set the param's value, i.e.
+/// `(* 0.5 (sin *time*))`.
+#[derive(Component, Clone, Default, Debug)]
+pub struct ParamExpression(pub String);
+
+impl ParamExpression {
+    /// Whether there's anything to evaluate, as an expression added in the editor starts empty.
+    pub fn is_active(&self) -> bool {
+        !self.0.trim().is_empty()
+    }
+}
+
+/// Makes a param follow the value of another op's param, i.e. `noise1.Seed` following
+/// `ctrl.Seed`. The op is referred to by name, so the link survives the op being restored.
+#[derive(Component, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParamLink {
+    pub op: String,
+    pub param: String,
+}
+
+impl ParamLink {
+    /// Find the param entity this link follows.
+    pub fn resolve(
+        &self,
+        op_name_idx: &UniqueIndex<OpName>,
+        param_idx: &CompositeIndex2<OpRef, ParamName>,
+    ) -> Option<Entity> {
+        let op = op_name_idx.get(&OpName(self.op.clone()))?;
+        param_idx
+            .get(&(OpRef(*op), ParamName(self.param.clone())))
+            .copied()
+    }
+}
+
+impl std::fmt::Display for ParamLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.op, self.param)
+    }
+}
+
+/// Marks a param set by `param!` in a script, until a frame passes without the script setting it.
 #[derive(Component, Default, Debug)]
 pub struct ScriptedParam;
 #[derive(Component, Default, Debug)]
 pub struct ScriptedParamError(pub String);
 
+/// Copy the value of each linked param from the param it follows. Links to params of a different
+/// type are ignored.
+pub fn follow_links(
+    links_q: Query<(
+        Entity,
+        &ParamLink,
+        Has<ScriptedParam>,
+        Option<&ParamExpression>,
+    )>,
+    mut params_q: Query<&mut ParamValue>,
+    op_name_idx: Res<UniqueIndex<OpName>>,
+    param_idx: Res<CompositeIndex2<OpRef, ParamName>>,
+) {
+    for (entity, link, scripted, expression) in links_q.iter() {
+        // Expressions and scripts are above links
+        if ParamLayer::driver(scripted, expression, true, false) != Some(ParamLayer::Link) {
+            continue;
+        }
+        let Some(source) = link.resolve(&op_name_idx, &param_idx) else {
+            continue;
+        };
+        let Ok(value) = params_q.get(source).cloned() else {
+            continue;
+        };
+        let Ok(mut param) = params_q.get_mut(entity) else {
+            continue;
+        };
+
+        if std::mem::discriminant(&value) == std::mem::discriminant(&*param) && *param != value {
+            *param = value;
+        }
+    }
+}
+
+/// Set overridden params to the value they're held at.
+pub fn apply_overrides(mut params_q: Query<(&ParamOverride, &mut ParamValue)>) {
+    for (held, mut param) in params_q.iter_mut() {
+        if std::mem::discriminant(&held.0) == std::mem::discriminant(&*param) && *param != held.0 {
+            *param = held.0.clone();
+        }
+    }
+}
+
+/// Record the highest layer driving each param.
+pub fn update_provenance(
+    mut params_q: Query<(
+        &mut ParamProvenance,
+        Has<ParamOverride>,
+        Has<ScriptedParam>,
+        Option<&ParamExpression>,
+        Has<ParamLink>,
+        Has<Keyframes>,
+    )>,
+) {
+    for (mut provenance, held, scripted, expression, linked, animated) in params_q.iter_mut() {
+        let active = if held {
+            ParamLayer::Override
+        } else {
+            ParamLayer::driver(scripted, expression, linked, animated).unwrap_or(provenance.base)
+        };
+        if provenance.active != active {
+            provenance.active = active;
+        }
+    }
+}
+
+/// Keep an edge from each op to the ops with params linked to it, so the ops it drives cook
+/// after it.
+pub fn update_link_edges(
+    mut graph_state: ResMut<GraphState>,
+    links_q: Query<(&ParamLink, &OpRef)>,
+    graph_id_q: Query<&GraphId>,
+    op_name_idx: Res<UniqueIndex<OpName>>,
+) {
+    let links = links_q
+        .iter()
+        .filter_map(|(link, op_ref)| {
+            let source = op_name_idx.get(&OpName(link.op.clone()))?;
+            let output = graph_id_q.get(*source).ok()?;
+            let input = graph_id_q.get(op_ref.0).ok()?;
+            (output != input).then_some((output.0, input.0))
+        })
+        .collect::<HashSet<_>>();
+
+    let stale = graph_state
+        .graph
+        .edge_references()
+        .filter(|edge| edge.weight().1 == LINK_PORT)
+        .filter(|edge| !links.contains(&(edge.source(), edge.target())))
+        .map(|edge| edge.id())
+        .collect::<Vec<_>>();
+    for edge in stale {
+        graph_state.graph.remove_edge(edge);
+    }
+    for (output, input) in links {
+        if graph_state
+            .find_edge(output, input, (0, LINK_PORT))
+            .is_none()
+        {
+            graph_state.graph.add_edge(output, input, (0, LINK_PORT));
+        }
+    }
+}
+
+/// Clamp changed params to their range, and clear references to ops that don't exist or that
+/// are the wrong kind of op, e.g. a mesh passed to a texture param.
 pub fn validate(
     mut commands: Commands,
-    mut params_q: Query<(Entity, &mut ParamValue), Changed<ParamValue>>,
-    category_q: Query<&OpCategory>,
+    mut params_q: Query<(Entity, &mut ParamValue, Option<&ParamMeta>), Changed<ParamValue>>,
+    op_q: Query<(&OpName, &OpCategory, &OpTypeName), Without<OpDespawn>>,
 ) {
-    for (entity, mut param_value) in params_q.iter_mut() {
+    for (entity, mut param_value, meta) in params_q.iter_mut() {
+        // Values loaded from a project may predate the param's range
+        if let Some(meta) = meta {
+            let mut value = param_value.clone();
+            if meta.clamp(&mut value) {
+                *param_value = value;
+            }
+        }
+
+        let Some(kind) = OpRefKind::of(&param_value) else {
+            continue;
+        };
+        // Only write back invalid values, so valid ones aren't marked changed again
+        let invalid = match &*param_value {
+            ParamValue::TextureOp(op) | ParamValue::MeshOp(op) | ParamValue::MaterialOp(op) => {
+                op.iter().copied().collect::<Vec<_>>()
+            }
+            ParamValue::CameraOps(ops) | ParamValue::LightOps(ops) => ops.clone(),
+            _ => vec![],
+        }
+        .into_iter()
+        .filter_map(|op| kind.check(op, &op_q).err().map(|e| (op, e)))
+        .collect::<Vec<_>>();
+        if invalid.is_empty() {
+            commands.entity(entity).remove::<ScriptedParamError>();
+            continue;
+        }
+
         match param_value.deref_mut() {
-            ParamValue::TextureOp(Some(e)) => {
-                let category = category_q.get(*e).unwrap();
-                if !category.is_texture() {
-                    commands
-                        .entity(entity)
-                        .insert(ScriptedParamError("Invalid texture".to_string()));
-                    *param_value = ParamValue::TextureOp(None);
-                }
+            ParamValue::TextureOp(op) | ParamValue::MeshOp(op) | ParamValue::MaterialOp(op) => {
+                *op = None;
             }
-            ParamValue::MeshOp(Some(e)) => {
-                let category = category_q.get(*e).unwrap();
-                if !category.is_mesh() {
-                    commands
-                        .entity(entity)
-                        .insert(ScriptedParamError("Invalid mesh".to_string()));
-                    *param_value = ParamValue::MeshOp(None);
-                }
+            ParamValue::CameraOps(ops) | ParamValue::LightOps(ops) => {
+                ops.retain(|op| invalid.iter().all(|(invalid, _)| invalid != op));
             }
             _ => {}
         }
+        let errors = invalid.into_iter().map(|(_, e)| e).collect::<Vec<_>>();
+        commands
+            .entity(entity)
+            .insert(ScriptedParamError(errors.join(", ")));
+    }
+}
+
+/// Clear references to ops that have been deleted, so params never hold a despawned entity.
+pub fn clear_dangling_refs(
+    mut removed: RemovedComponents<OpName>,
+    mut params_q: Query<&mut ParamValue>,
+) {
+    let removed = removed.read().collect::<HashSet<_>>();
+    if removed.is_empty() {
+        return;
+    }
+
+    for mut value in params_q.iter_mut() {
+        let dangling = match &*value {
+            ParamValue::TextureOp(Some(op))
+            | ParamValue::MeshOp(Some(op))
+            | ParamValue::MaterialOp(Some(op)) => removed.contains(op),
+            ParamValue::CameraOps(ops) | ParamValue::LightOps(ops) => {
+                ops.iter().any(|op| removed.contains(op))
+            }
+            _ => false,
+        };
+        if !dangling {
+            continue;
+        }
+
+        match value.deref_mut() {
+            ParamValue::TextureOp(op) | ParamValue::MeshOp(op) | ParamValue::MaterialOp(op) => {
+                *op = None;
+            }
+            ParamValue::CameraOps(ops) | ParamValue::LightOps(ops) => {
+                ops.retain(|op| !removed.contains(op));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The kind of op a reference param accepts.
+#[derive(Clone, Copy, Debug)]
+enum OpRefKind {
+    Texture,
+    Mesh,
+    Material,
+    Camera,
+    Light,
+}
+
+impl OpRefKind {
+    fn of(value: &ParamValue) -> Option<Self> {
+        match value {
+            ParamValue::TextureOp(_) => Some(OpRefKind::Texture),
+            ParamValue::MeshOp(_) => Some(OpRefKind::Mesh),
+            ParamValue::MaterialOp(_) => Some(OpRefKind::Material),
+            ParamValue::CameraOps(_) => Some(OpRefKind::Camera),
+            ParamValue::LightOps(_) => Some(OpRefKind::Light),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            OpRefKind::Texture => "texture",
+            OpRefKind::Mesh => "mesh",
+            OpRefKind::Material => "material",
+            OpRefKind::Camera => "camera",
+            OpRefKind::Light => "light",
+        }
+    }
+
+    fn accepts(&self, category: &OpCategory, type_name: &OpTypeName) -> bool {
+        match self {
+            OpRefKind::Texture => category.is_texture(),
+            OpRefKind::Mesh => category.is_mesh(),
+            OpRefKind::Material => category.is_material(),
+            OpRefKind::Camera => type_name.0 == ComponentOpCamera::NAME,
+            OpRefKind::Light => type_name.0 == ComponentOpLight::NAME,
+        }
+    }
+
+    /// Check that an entity is an op this kind of param can reference.
+    fn check(
+        &self,
+        op: Entity,
+        op_q: &Query<(&OpName, &OpCategory, &OpTypeName), Without<OpDespawn>>,
+    ) -> Result<(), String> {
+        let Ok((name, category, type_name)) = op_q.get(op) else {
+            return Err(format!("{} is not an op", op));
+        };
+        if !self.accepts(category, type_name) {
+            return Err(format!("{} is not a {}", name.0, self.as_str()));
+        }
+        Ok(())
     }
 }
 
@@ -257,6 +899,11 @@ impl<'w, 's> Params<'w, 's> {
             .get(&(OpRef(entity), ParamName(name.into())))
             .map(|e| self.params_q.get_mut(*e).unwrap())
     }
+
+    /// Read the params of an op into their struct.
+    pub fn read<T: OpParams>(&self, entity: Entity) -> T {
+        T::read(self, entity)
+    }
 }
 
 pub trait IntoParams {
@@ -271,18 +918,21 @@ impl IntoParams for Transform {
                 name: ParamName("Translation".to_string()),
                 value: ParamValue::Vec3(self.translation),
                 order: ParamOrder(0),
+                meta: ParamMeta::default().section("Transform"),
                 ..default()
             },
             ParamBundle {
                 name: ParamName("Rotation".to_string()),
                 value: ParamValue::Quat(self.rotation),
                 order: ParamOrder(1),
+                meta: ParamMeta::default().section("Transform"),
                 ..default()
             },
             ParamBundle {
                 name: ParamName("Scale".to_string()),
                 value: ParamValue::Vec3(self.scale),
                 order: ParamOrder(2),
+                meta: ParamMeta::default().section("Transform"),
                 ..default()
             },
         ]
@@ -0,0 +1,276 @@
+use std::collections::BTreeMap;
+
+use bevy::ecs::system::SystemState;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::engine::animation::{lerp, Keyframes};
+use crate::engine::history::{Edit, History};
+use crate::engine::op::{OpName, OpRef};
+use crate::engine::param::{
+    follow_links, ParamExpression, ParamLayer, ParamLink, ParamMeta, ParamName, ParamOverride,
+    ParamValue, ScriptedParam,
+};
+use crate::engine::project::ParamDataValue;
+use crate::index::{CompositeIndex2, UniqueIndex};
+use crate::Sets;
+
+pub struct PresetPlugin;
+
+impl Plugin for PresetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Presets>()
+            .init_resource::<PresetMorph>()
+            .add_systems(
+                Update,
+                morph
+                    .in_set(Sets::Params)
+                    // Linked params follow the morphed value
+                    .before(follow_links),
+            );
+    }
+}
+
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+// Resources
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+/// The param values of an op, or of a container and every op inside it.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Preset {
+    /// The op the preset was captured from.
+    pub op: String,
+    /// The values of each op's params, by op and param name.
+    pub values: BTreeMap<String, BTreeMap<String, ParamDataValue>>,
+}
+
+/// Named presets, which are saved with the project.
+#[derive(Resource, Deref, DerefMut, Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Presets(pub BTreeMap<String, Preset>);
+
+/// A preset being recalled, blending params from the values they had to the preset's.
+#[derive(Resource, Default, Debug)]
+pub struct PresetMorph(Option<Morph>);
+
+impl PresetMorph {
+    /// The name of the preset being recalled.
+    pub fn preset(&self) -> Option<&str> {
+        self.0.as_ref().map(|morph| morph.preset.as_str())
+    }
+
+    /// How far along the recall is, from 0 to 1.
+    pub fn progress(&self) -> Option<f32> {
+        self.0.as_ref().map(Morph::progress)
+    }
+}
+
+#[derive(Debug)]
+struct Morph {
+    preset: String,
+    params: Vec<MorphParam>,
+    elapsed: f32,
+    duration: f32,
+}
+
+impl Morph {
+    fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            1.0
+        } else {
+            (self.elapsed / self.duration).min(1.0)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct MorphParam {
+    param: Entity,
+    from: ParamValue,
+    to: ParamValue,
+    /// Whether the value blends, rather than switching at the end like op references. Blending
+    /// choices would pass through every choice in between.
+    blend: bool,
+    /// Recorded in the history once the recall finishes.
+    edit: Edit,
+}
+
+#[non_exhaustive]
+#[derive(Debug, thiserror::Error)]
+pub enum PresetError {
+    #[error("No op named {0}")]
+    OpNotFound(String),
+    #[error("No preset named {0}")]
+    PresetNotFound(String),
+}
+
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+// Systems
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+/// Blend the params of the preset being recalled. Values that can't be blended, i.e. op
+/// references, switch once the recall finishes. Morphs run in real time, so they still finish
+/// while the timeline is paused.
+fn morph(
+    time: Res<Time<Real>>,
+    mut morph: ResMut<PresetMorph>,
+    mut params_q: Query<&mut ParamValue>,
+    mut history: ResMut<History>,
+) {
+    let Some(active) = morph.0.as_mut() else {
+        return;
+    };
+
+    active.elapsed += time.delta_seconds();
+    let t = active.progress();
+    for param in &active.params {
+        let Ok(mut value) = params_q.get_mut(param.param) else {
+            continue;
+        };
+        let blended = if t >= 1.0 {
+            param.to.clone()
+        } else if param.blend {
+            lerp(&param.from, &param.to, t, true)
+        } else {
+            param.from.clone()
+        };
+        if *value != blended {
+            *value = blended;
+        }
+    }
+
+    if t >= 1.0 {
+        let Some(finished) = morph.0.take() else {
+            return;
+        };
+        let edits = finished
+            .params
+            .into_iter()
+            .map(|param| param.edit)
+            .collect();
+        history.record(Edit::Batch(edits));
+    }
+}
+
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+// Presets
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+/// Capture the params of an op as a preset, replacing any preset with the same name. When the
+/// op is a container, the ops inside it are captured too.
+pub fn save_preset(world: &mut World, name: String, op: &str) -> Result<(), PresetError> {
+    let mut state = SystemState::<(
+        Res<UniqueIndex<OpName>>,
+        Query<&Children>,
+        Query<(&ParamName, &ParamValue)>,
+        Query<&OpName>,
+    )>::new(world);
+    let (op_name_idx, children_q, params_q, name_q) = state.get(world);
+
+    let prefix = format!("{}{}", op, OpName::SEPARATOR);
+    let mut values = BTreeMap::new();
+    for (name, entity) in op_name_idx.iter() {
+        if name.0 != op && !name.0.starts_with(&prefix) {
+            continue;
+        }
+
+        let params = children_q
+            .get(*entity)
+            .into_iter()
+            .flat_map(|children| children.iter())
+            .filter_map(|param| params_q.get(*param).ok())
+            // Pulses only last a frame, so recalling them would retrigger them
+            .filter(|(_, value)| !matches!(value, ParamValue::Pulse(_)))
+            .map(|(param, value)| (param.0.clone(), ParamDataValue::from_param(value, &name_q)))
+            .collect();
+        values.insert(name.0.clone(), params);
+    }
+    if values.is_empty() {
+        return Err(PresetError::OpNotFound(op.to_string()));
+    }
+
+    let preset = Preset {
+        op: op.to_string(),
+        values,
+    };
+    world.resource_mut::<Presets>().insert(name, preset);
+    Ok(())
+}
+
+/// Recall a preset, blending the params to its values over a number of seconds, or setting
+/// them immediately when it's 0. Ops that no longer exist are skipped.
+pub fn recall_preset(world: &mut World, name: &str, seconds: f32) -> Result<(), PresetError> {
+    // Scripts recall presets every frame, which mustn't restart the morph
+    if world.resource::<PresetMorph>().preset() == Some(name) {
+        return Ok(());
+    }
+
+    let Some(preset) = world.resource::<Presets>().get(name).cloned() else {
+        return Err(PresetError::PresetNotFound(name.to_string()));
+    };
+
+    let mut state = SystemState::<(
+        Res<UniqueIndex<OpName>>,
+        Res<CompositeIndex2<OpRef, ParamName>>,
+        Query<(
+            &ParamValue,
+            Option<&ParamMeta>,
+            Has<ParamOverride>,
+            Has<ScriptedParam>,
+            Option<&ParamExpression>,
+            Has<ParamLink>,
+            Has<Keyframes>,
+        )>,
+        Query<&OpName>,
+    )>::new(world);
+    let (op_name_idx, param_idx, value_q, name_q) = state.get(world);
+
+    let mut params = vec![];
+    for (op, values) in &preset.values {
+        let Some(entity) = op_name_idx.get(&OpName(op.clone())) else {
+            continue;
+        };
+        for (param_name, data) in values {
+            let Some(param) = param_idx.get(&(OpRef(*entity), ParamName(param_name.clone())))
+            else {
+                continue;
+            };
+            let Ok((value, meta, held, scripted, expression, linked, animated)) =
+                value_q.get(*param)
+            else {
+                continue;
+            };
+            // A param driven by a higher layer would be set straight back
+            if held || ParamLayer::driver(scripted, expression, linked, animated).is_some() {
+                continue;
+            }
+
+            let mut to = value.clone();
+            if !data.apply(&mut to, &op_name_idx) || to == *value {
+                continue;
+            }
+            params.push(MorphParam {
+                param: *param,
+                from: value.clone(),
+                to,
+                blend: meta.map_or(true, |meta| meta.choices.is_empty()),
+                edit: Edit::Param {
+                    op: OpName(op.clone()),
+                    param: param_name.clone(),
+                    from: ParamDataValue::from_param(value, &name_q),
+                    to: data.clone(),
+                },
+            });
+        }
+    }
+
+    if params.is_empty() {
+        return Ok(());
+    }
+    world.resource_mut::<PresetMorph>().0 = Some(Morph {
+        preset: name.to_string(),
+        params,
+        elapsed: 0.0,
+        duration: seconds.max(0.0),
+    });
+    Ok(())
+}
@@ -0,0 +1,599 @@
+use std::path::PathBuf;
+
+use bevy::asset::ron;
+use bevy::asset::ron::ser::PrettyConfig;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use serde::{Deserialize, Serialize};
+
+use crate::engine::animation::{Keyframes, Timeline};
+use crate::engine::graph::event::Connect;
+use crate::engine::graph::GraphId;
+use crate::engine::op::{
+    spawn_op, OpBypass, OpDynExecute, OpInputs, OpName, OpOwner, OpRef, OpTypeName,
+};
+use crate::engine::param::{
+    ParamExpression, ParamLayer, ParamLink, ParamName, ParamOrder, ParamPage, ParamProvenance,
+    ParamValue,
+};
+use crate::engine::preset::Presets;
+use crate::engine::script::asset::{Script, ScriptEntryPath};
+use crate::index::{CompositeIndex2, UniqueIndex};
+use crate::ui::graph::{NodePosition, NodeRoot, UiRef};
+use crate::Sets;
+
+/// The current version of the project file format.
+pub const PROJECT_VERSION: u32 = 1;
+
+pub struct ProjectPlugin;
+
+impl Plugin for ProjectPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ProjectPath>()
+            .init_resource::<PendingConnections>()
+            .add_event::<SaveProject>()
+            .add_event::<LoadProject>()
+            .add_systems(Startup, load_on_startup)
+            .add_systems(
+                Update,
+                (
+                    (
+                        hotkeys,
+                        save.run_if(on_event::<SaveProject>()),
+                        load.run_if(on_event::<LoadProject>()),
+                    )
+                        .chain()
+                        .in_set(Sets::Ui),
+                    (apply_pending_params, apply_pending_connections)
+                        .chain()
+                        .in_set(Sets::Graph),
+                ),
+            );
+    }
+}
+
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+// Events
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+/// Write the current op network to the [ProjectPath].
+#[derive(Event, Default, Debug, Clone, Copy)]
+pub struct SaveProject;
+
+/// Rebuild the op network from the [ProjectPath].
+#[derive(Event, Default, Debug, Clone, Copy)]
+pub struct LoadProject;
+
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+// Components
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+/// Params waiting to be applied once the op has finished spawning.
+#[derive(Component, Debug)]
+struct PendingParams(Vec<ParamData>);
+
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+// Resources
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+/// The path of the project file to save to and load from.
+#[derive(Resource, Deref, DerefMut, Debug, Clone)]
+pub struct ProjectPath(pub PathBuf);
+
+impl Default for ProjectPath {
+    fn default() -> Self {
+        Self(PathBuf::from("assets/project.ron"))
+    }
+}
+
+/// Connections waiting for both of their ops to be ready.
+#[derive(Resource, Default, Debug)]
+struct PendingConnections(Vec<ConnectionData>);
+
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+// File format
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProjectFile {
+    pub version: u32,
+    pub ops: Vec<OpData>,
+    pub connections: Vec<ConnectionData>,
+    #[serde(default)]
+    pub timeline: Timeline,
+    #[serde(default)]
+    pub presets: Presets,
+    /// The script run for the whole project, relative to the assets folder. Projects without
+    /// one run the default [ScriptEntryPath].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpData {
+    pub name: String,
+    /// The script type name of the op, i.e. `mesh-noise`.
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub position: Option<Vec2>,
+    #[serde(default)]
+    pub bypass: bool,
+    /// The script run as the op's behaviour, relative to the assets folder.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<PathBuf>,
+    pub params: Vec<ParamData>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ParamData {
+    pub name: String,
+    pub value: ParamDataValue,
+    pub order: u32,
+    pub page: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expression: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<ParamLink>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyframes: Option<Keyframes>,
+}
+
+/// A param value as written to disk. Entities aren't stable across runs, so op references
+/// are stored by [OpName].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ParamDataValue {
+    Value(ParamValue),
+    Op(Option<String>),
+    Ops(Vec<String>),
+}
+
+impl ParamDataValue {
+    pub fn from_param(value: &ParamValue, name_q: &Query<&OpName>) -> Self {
+        let name = |entity: &Entity| name_q.get(*entity).ok().map(|name| name.0.clone());
+        match value {
+            ParamValue::TextureOp(entity)
+            | ParamValue::MeshOp(entity)
+            | ParamValue::MaterialOp(entity) => ParamDataValue::Op(entity.as_ref().and_then(name)),
+            ParamValue::CameraOps(entities) | ParamValue::LightOps(entities) => {
+                ParamDataValue::Ops(entities.iter().filter_map(name).collect())
+            }
+            value => ParamDataValue::Value(value.clone()),
+        }
+    }
+
+    /// Apply the stored value to a param, resolving op references by name. Returns false if
+    /// the stored value doesn't match the param's type.
+    pub fn apply(&self, param: &mut ParamValue, op_name_idx: &UniqueIndex<OpName>) -> bool {
+        let entity = |name: &String| op_name_idx.get(&OpName(name.clone())).copied();
+        match (self, param) {
+            (ParamDataValue::Value(value), param) => {
+                if std::mem::discriminant(value) != std::mem::discriminant(param) {
+                    return false;
+                }
+                *param = value.clone();
+            }
+            (ParamDataValue::Op(name), ParamValue::TextureOp(x))
+            | (ParamDataValue::Op(name), ParamValue::MeshOp(x))
+            | (ParamDataValue::Op(name), ParamValue::MaterialOp(x)) => {
+                *x = name.as_ref().and_then(entity);
+            }
+            (ParamDataValue::Ops(names), ParamValue::CameraOps(x))
+            | (ParamDataValue::Ops(names), ParamValue::LightOps(x)) => {
+                *x = names.iter().filter_map(entity).collect();
+            }
+            _ => return false,
+        }
+
+        true
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ConnectionData {
+    pub output: String,
+    pub output_port: u8,
+    pub input: String,
+    pub input_port: u8,
+}
+
+#[non_exhaustive]
+#[derive(Debug, thiserror::Error)]
+pub enum ProjectError {
+    /// An [IO](std::io) Error
+    #[error("Could not access project file: {0}")]
+    Io(#[from] std::io::Error),
+    /// A [ron::error::SpannedError]
+    #[error("Could not parse project file: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    /// A [ron::Error]
+    #[error("Could not serialize project: {0}")]
+    Serialize(#[from] ron::Error),
+    /// The project was written by an unsupported version
+    #[error("Unsupported project version {0}, expected {}", PROJECT_VERSION)]
+    Version(u32),
+}
+
+impl ProjectFile {
+    pub fn read(path: &PathBuf) -> Result<Self, ProjectError> {
+        let contents = std::fs::read_to_string(path)?;
+        let project: ProjectFile = ron::de::from_str(&contents)?;
+        if project.version != PROJECT_VERSION {
+            return Err(ProjectError::Version(project.version));
+        }
+        Ok(project)
+    }
+
+    pub fn write(&self, path: &PathBuf) -> Result<(), ProjectError> {
+        let contents = ron::ser::to_string_pretty(self, PrettyConfig::default())?;
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+/// Reads ops in the form they are written to disk, i.e. to save a project or to restore an op
+/// that was deleted.
+#[derive(SystemParam)]
+pub struct OpSnapshot<'w, 's> {
+    op_q: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static OpName,
+            &'static OpTypeName,
+            Option<&'static OpInputs>,
+            Option<&'static UiRef>,
+            Option<&'static Children>,
+            Has<OpBypass>,
+            Option<&'static Handle<Script>>,
+        ),
+    >,
+    name_q: Query<'w, 's, &'static OpName>,
+    owner_q: Query<'w, 's, &'static OpOwner>,
+    param_q: Query<
+        'w,
+        's,
+        (
+            &'static ParamName,
+            &'static ParamValue,
+            &'static ParamOrder,
+            &'static ParamPage,
+            Option<&'static ParamExpression>,
+            Option<&'static ParamLink>,
+            Option<&'static Keyframes>,
+        ),
+    >,
+    node_q: Query<'w, 's, &'static Transform, With<NodeRoot>>,
+}
+
+impl OpSnapshot<'_, '_> {
+    /// The ops saved with the project, sorted by name.
+    pub fn ops(&self) -> Vec<OpData> {
+        let mut ops = self
+            .op_q
+            .iter()
+            .filter(|(entity, ..)| self.owner_q.get(*entity).is_ok_and(OpOwner::is_saved))
+            .filter_map(|(entity, ..)| self.op(entity))
+            .collect::<Vec<_>>();
+        ops.sort_by(|a, b| a.name.cmp(&b.name));
+        ops
+    }
+
+    pub fn op(&self, entity: Entity) -> Option<OpData> {
+        let (_, name, type_name, _, ui_ref, children, bypass, script) =
+            self.op_q.get(entity).ok()?;
+
+        let mut params = children
+            .iter()
+            .flat_map(|children| children.iter())
+            .filter_map(|child| self.param_q.get(*child).ok())
+            .map(
+                |(param_name, value, order, page, expression, link, keyframes)| ParamData {
+                    name: param_name.0.clone(),
+                    value: ParamDataValue::from_param(value, &self.name_q),
+                    order: order.0,
+                    page: page.0.clone(),
+                    expression: expression.map(|expression| expression.0.clone()),
+                    link: link.cloned(),
+                    keyframes: keyframes.cloned(),
+                },
+            )
+            .collect::<Vec<_>>();
+        params.sort_by(|a, b| a.order.cmp(&b.order).then_with(|| a.name.cmp(&b.name)));
+
+        let position = ui_ref
+            .and_then(|ui_ref| self.node_q.get(ui_ref.0).ok())
+            .map(|transform| transform.translation.xy());
+
+        Some(OpData {
+            name: name.0.clone(),
+            ty: type_name.0.to_string(),
+            position,
+            bypass,
+            script: script
+                .and_then(|script| script.path())
+                .map(|path| path.path().to_path_buf()),
+            params,
+        })
+    }
+
+    /// The connections into an op.
+    pub fn inputs(&self, entity: Entity) -> Vec<ConnectionData> {
+        let Ok((_, name, _, Some(inputs), ..)) = self.op_q.get(entity) else {
+            return vec![];
+        };
+
+        inputs
+            .connections
+            .iter()
+            .filter_map(|(input_port, (output, output_port))| {
+                let output = self.name_q.get(*output).ok()?;
+                Some(ConnectionData {
+                    output: output.0.clone(),
+                    output_port: *output_port,
+                    input: name.0.clone(),
+                    input_port: *input_port,
+                })
+            })
+            .collect()
+    }
+
+    /// All connections between ops, sorted.
+    pub fn connections(&self) -> Vec<ConnectionData> {
+        let mut connections = self
+            .op_q
+            .iter()
+            .flat_map(|(entity, ..)| self.inputs(entity))
+            .collect::<Vec<_>>();
+        connections.sort();
+        connections
+    }
+
+    /// The connections into and out of an op.
+    pub fn op_connections(&self, entity: Entity) -> Vec<ConnectionData> {
+        let Ok(name) = self.name_q.get(entity) else {
+            return vec![];
+        };
+
+        self.connections()
+            .into_iter()
+            .filter(|connection| connection.output == name.0 || connection.input == name.0)
+            .collect()
+    }
+}
+
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+// Systems
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+fn load_on_startup(path: Res<ProjectPath>, mut ev_load: EventWriter<LoadProject>) {
+    if path.exists() {
+        ev_load.send(LoadProject);
+    }
+}
+
+fn hotkeys(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut ev_save: EventWriter<SaveProject>,
+    mut ev_load: EventWriter<LoadProject>,
+) {
+    let modifier = keys.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ]);
+    if !modifier {
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::KeyS) {
+        ev_save.send(SaveProject);
+    } else if keys.just_pressed(KeyCode::KeyO) {
+        ev_load.send(LoadProject);
+    }
+}
+
+fn save(
+    mut ev_save: EventReader<SaveProject>,
+    path: Res<ProjectPath>,
+    snapshot: OpSnapshot,
+    timeline: Res<Timeline>,
+    presets: Res<Presets>,
+    entry_path: Res<ScriptEntryPath>,
+) {
+    ev_save.clear();
+
+    // Connections touching ops that aren't saved are made by whatever created those ops
+    let ops = snapshot.ops();
+    let saved = ops.iter().map(|op| &op.name).collect::<HashSet<_>>();
+    let connections = snapshot
+        .connections()
+        .into_iter()
+        .filter(|connection| {
+            saved.contains(&connection.output) && saved.contains(&connection.input)
+        })
+        .collect();
+    let project = ProjectFile {
+        version: PROJECT_VERSION,
+        ops,
+        connections,
+        timeline: timeline.clone(),
+        presets: presets.clone(),
+        script: Some(entry_path.0.clone()),
+    };
+    match project.write(&path) {
+        Ok(()) => info!("Saved project to {:?}", path.0),
+        Err(err) => error!("Failed to save project: {}", err),
+    }
+}
+
+fn load(mut commands: Commands, mut ev_load: EventReader<LoadProject>, path: Res<ProjectPath>) {
+    ev_load.clear();
+
+    match ProjectFile::read(&path) {
+        Ok(project) => {
+            info!("Loading project from {:?}", path.0);
+            commands.add(move |world: &mut World| load_project(world, project));
+        }
+        Err(err) => error!("Failed to load project: {}", err),
+    }
+}
+
+fn load_project(world: &mut World, project: ProjectFile) {
+    world.insert_resource(project.timeline);
+    world.insert_resource(project.presets);
+    // Only replace the entry script when it changes, as that reloads it
+    if let Some(path) = project.script {
+        if world.resource::<ScriptEntryPath>().0 != path {
+            world.insert_resource(ScriptEntryPath(path));
+        }
+    }
+    for op in project.ops {
+        restore_op(world, op);
+    }
+
+    restore_connections(world, project.connections);
+}
+
+/// Spawn an op from its data, or update it if it already exists. Its params are applied once
+/// it has finished spawning.
+pub fn restore_op(world: &mut World, op: OpData) -> Option<Entity> {
+    let name = OpName(op.name);
+    let existing = world.resource::<UniqueIndex<OpName>>().get(&name).copied();
+    let mut entity = match existing {
+        Some(entity) => world.entity_mut(entity),
+        None => match spawn_op(world, &op.ty, name.clone()) {
+            Some(entity) => entity,
+            None => {
+                warn!("Skipping op {} with unknown type {}", name.0, op.ty);
+                return None;
+            }
+        },
+    };
+
+    entity.insert((OpOwner::User, PendingParams(op.params)));
+    if let Some(position) = op.position {
+        entity.insert(NodePosition(position));
+    }
+    if op.bypass {
+        entity.insert(OpBypass);
+    } else {
+        entity.remove::<OpBypass>();
+    }
+    match op.script {
+        Some(path) => {
+            let script = entity
+                .world()
+                .resource::<AssetServer>()
+                .load::<Script>(path);
+            entity.insert(script);
+        }
+        None => {
+            entity.remove::<Handle<Script>>();
+        }
+    }
+
+    Some(entity.id())
+}
+
+/// Connect ops once both of them are ready.
+pub fn restore_connections(
+    world: &mut World,
+    connections: impl IntoIterator<Item = ConnectionData>,
+) {
+    world
+        .resource_mut::<PendingConnections>()
+        .0
+        .extend(connections);
+}
+
+fn apply_pending_params(
+    mut commands: Commands,
+    pending_q: Query<(Entity, &PendingParams), With<OpDynExecute>>,
+    mut param_q: Query<(&mut ParamValue, &mut ParamOrder, &mut ParamPage)>,
+    param_idx: Res<CompositeIndex2<OpRef, ParamName>>,
+    op_name_idx: Res<UniqueIndex<OpName>>,
+) {
+    for (entity, pending) in pending_q.iter() {
+        for data in pending.0.iter() {
+            let Some(param) = param_idx.get(&(OpRef(entity), ParamName(data.name.clone()))) else {
+                warn!("Skipping unknown param {}", data.name);
+                continue;
+            };
+            let Ok((mut value, mut order, mut page)) = param_q.get_mut(*param) else {
+                continue;
+            };
+
+            if !data.value.apply(&mut value, &op_name_idx) {
+                warn!("Skipping param {} with mismatched type", data.name);
+            }
+            order.0 = data.order;
+            page.0 = data.page.clone();
+            commands.entity(*param).insert(ParamProvenance {
+                base: ParamLayer::Saved,
+                active: ParamLayer::Saved,
+            });
+            match &data.expression {
+                Some(expression) => {
+                    commands
+                        .entity(*param)
+                        .insert(ParamExpression(expression.clone()));
+                }
+                None => {
+                    commands.entity(*param).remove::<ParamExpression>();
+                }
+            }
+            match &data.link {
+                Some(link) => {
+                    commands.entity(*param).insert(link.clone());
+                }
+                None => {
+                    commands.entity(*param).remove::<ParamLink>();
+                }
+            }
+            match &data.keyframes {
+                Some(keyframes) => {
+                    commands.entity(*param).insert(keyframes.clone());
+                }
+                None => {
+                    commands.entity(*param).remove::<Keyframes>();
+                }
+            }
+        }
+
+        commands.entity(entity).remove::<PendingParams>();
+    }
+}
+
+fn apply_pending_connections(
+    mut pending: ResMut<PendingConnections>,
+    op_name_idx: Res<UniqueIndex<OpName>>,
+    ready_q: Query<(), (With<GraphId>, With<OpDynExecute>, With<UiRef>)>,
+    mut ev_connect: EventWriter<Connect>,
+) {
+    pending.0.retain(|connection| {
+        let output = op_name_idx.get(&OpName(connection.output.clone()));
+        let input = op_name_idx.get(&OpName(connection.input.clone()));
+        let (Some(output), Some(input)) = (output, input) else {
+            warn!(
+                "Dropping connection from {} to {}",
+                connection.output, connection.input
+            );
+            return false;
+        };
+
+        if !ready_q.contains(*output) || !ready_q.contains(*input) {
+            return true;
+        }
+
+        ev_connect.send(Connect {
+            output: *output,
+            input: *input,
+            output_port: connection.output_port,
+            input_port: connection.input_port,
+        });
+        false
+    });
+}
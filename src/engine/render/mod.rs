@@ -5,8 +5,9 @@ use std::hash::{BuildHasherDefault, Hash, Hasher};
 
 use crate::engine::graph::update_graph;
 use crate::engine::graph::GraphState;
-use crate::engine::op::texture::TextureOp;
-use crate::engine::op::OpName;
+use crate::engine::op::texture::render::TextureOpInputImages;
+use crate::engine::op::texture::{self, TextureOp};
+use crate::engine::op::{OpDelayedInputs, OpName};
 use crate::engine::param::Params;
 use crate::Sets;
 
@@ -14,51 +15,34 @@ pub struct RenderPlugin;
 
 impl Plugin for RenderPlugin {
     fn build(&self, app: &mut App) {
-        // app.add_systems(Update,
-        //
-        //                 (order_cameras).in_set(Sets::Params)
-        //
-        // );
+        app.add_systems(Update, order_cameras.in_set(Sets::Graph));
     }
 }
 
-// fn params_hash(mut params_hash: ResMut<ParamsHash>, mut query: Query<(Entity, &mut Camera), With<OpName>>, params: Params) {
-//     for (entity, mut camera) in query.iter_mut() {
-//         let mut params = params.get_all(entity.clone());
-//         let prev_hash = params_hash.hashes.get(&entity);
-//         let mut hasher = AHasher::default();
-//         params.hash(&mut hasher);
-//         let new_hash = hasher.finish();
-//         if let None = prev_hash {
-//             params_hash.hashes.insert(entity, new_hash);
-//             camera.output_mode = CameraOutputMode::default();
-//         } else if let Some(prev_hash) = prev_hash {
-//             if *prev_hash != new_hash {
-//                 params_hash.hashes.insert(entity, new_hash);
-//                 camera.output_mode = CameraOutputMode::default();
-//             } else {
-//                 camera.output_mode = CameraOutputMode::Skip;
-//             }
-//         }
-//     }
-// }
+/// Order texture op cameras by the execution order, so an op renders after its inputs in the
+/// same frame rather than reading their previous output. Feedback ops read the previous frame,
+/// so they keep rendering first.
+pub fn order_cameras(
+    graph: Res<GraphState>,
+    mut camera_q: Query<(&mut Camera, Has<OpDelayedInputs>), With<TextureOpInputImages>>,
+    delayed_q: Query<(), With<OpDelayedInputs>>,
+) {
+    // A cycle is reported when ops execute
+    let Ok(sorted) = graph.execution_order(|entity| delayed_q.contains(entity)) else {
+        return;
+    };
 
-fn order_cameras(graph: Res<GraphState>, mut cameras: Query<&mut Camera>) {
-    let sorted = petgraph::algo::toposort(&graph.graph, None);
-    match sorted {
-        Ok(sorted) => {
-            for (i, index) in sorted.iter().enumerate() {
-                let entity = graph
-                    .entity_map
-                    .get(index)
-                    .expect(format!("Failed to get entity for index {:?}", index).as_str());
-                if let Ok(mut camera) = cameras.get_mut(*entity) {
-                    camera.order = i as isize;
-                }
-            }
+    let mut order = texture::CAMERA_ORDER;
+    for entity in sorted {
+        let Ok((mut camera, delayed)) = camera_q.get_mut(entity) else {
+            continue;
+        };
+        if delayed {
+            continue;
         }
-        Err(e) => {
-            error!("Failed to sort cameras: {:?}", e)
+        if camera.order != order {
+            camera.order = order;
         }
+        order += 1;
     }
 }
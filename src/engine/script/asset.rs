@@ -1,10 +1,18 @@
+use std::path::{Component, Path, PathBuf};
+
 use bevy::asset::io::Reader;
-use bevy::asset::{ron, AssetLoader, AsyncReadExt, LoadContext};
+use bevy::asset::{ron, AssetLoader, AsyncReadExt, LoadContext, ReadAssetBytesError};
 use bevy::prelude::*;
 use bevy::utils::{BoxedFuture, HashMap};
 use steel::compiler::program::RawProgramWithSymbols;
+use steel::rerrs::SteelErr;
+use steel::steel_vm::engine::Engine;
+
+use crate::engine::script::errors::{ScriptErrorReport, ScriptErrorStage, ScriptErrors};
+use crate::engine::script::{despawn_owned_ops, LispEngine};
 
-use crate::engine::script::LispEngine;
+/// The folder asset paths are relative to.
+pub(crate) const ASSETS_DIR: &str = "assets";
 
 pub struct ScriptAssetPlugin;
 
@@ -12,54 +20,90 @@ impl Plugin for ScriptAssetPlugin {
     fn build(&self, app: &mut App) {
         app.init_asset::<Script>()
             .init_asset_loader::<ScriptLoader>()
+            .init_resource::<ScriptEntryPath>()
             .init_non_send_resource::<ProgramCache>()
-            .add_systems(Startup, setup)
-            .add_systems(Update, load_scripts);
+            .add_systems(
+                Update,
+                (
+                    load_entry_script.run_if(resource_changed::<ScriptEntryPath>),
+                    load_scripts,
+                ),
+            );
     }
 }
 
 #[derive(Default, Deref, DerefMut)]
 pub struct ProgramCache(HashMap<AssetId<Script>, RawProgramWithSymbols>);
 
-fn setup(mut commands: Commands, mut asset_server: ResMut<AssetServer>) {
-    let script: Handle<Script> = asset_server.load("project.scm");
-    commands.spawn((script,));
+/// The script run for the whole project, relative to the assets folder, as set by the project
+/// file. Changing it unloads the previous script.
+#[derive(Resource, Deref, DerefMut, Debug, Clone)]
+pub struct ScriptEntryPath(pub PathBuf);
+
+impl Default for ScriptEntryPath {
+    fn default() -> Self {
+        Self(PathBuf::from("project.scm"))
+    }
+}
+
+/// Marks the entity holding the handle of the [ScriptEntryPath].
+#[derive(Component)]
+struct EntryScript;
+
+fn load_entry_script(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    path: Res<ScriptEntryPath>,
+    entry_q: Query<Entity, With<EntryScript>>,
+) {
+    for entity in entry_q.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let script: Handle<Script> = asset_server.load(path.0.clone());
+    commands.spawn((EntryScript, script));
 }
 
 pub fn load_scripts(
+    mut commands: Commands,
     mut engine: NonSendMut<LispEngine>,
     mut program_cache: NonSendMut<ProgramCache>,
     scripts: Res<Assets<Script>>,
+    mut script_errors: ResMut<ScriptErrors>,
     mut ev_asset: EventReader<AssetEvent<Script>>,
 ) {
     let mut engine = engine.borrow_mut();
     for ev in ev_asset.read() {
         match ev {
-            AssetEvent::Added { id } => {
-                let script = scripts.get(*id).unwrap();
-                let program = match engine.emit_raw_program_no_path(script.code.clone()) {
-                    Ok(program) => program,
-                    Err(err) => {
-                        error!("Failed to compile script: {:?}", err);
-                        continue;
-                    }
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => {
+                // Errors from the previous version are stale once it's reloaded
+                script_errors.clear_script(*id);
+                let Some(script) = scripts.get(*id) else {
+                    continue;
                 };
-                program_cache.insert(*id, program);
-                info!("Added script: {:?}", id);
-            }
-            AssetEvent::Modified { id } => {
-                let script = scripts.get(*id).unwrap();
-                let program = match engine.emit_raw_program_no_path(script.code.clone()) {
-                    Ok(program) => program,
+                match compile(&mut engine, script) {
+                    Ok(program) => {
+                        program_cache.insert(*id, program);
+                        info!("Loaded script: {:?}", id);
+                    }
                     Err(err) => {
-                        error!("Failed to compile script: {:?}", err);
-                        continue;
+                        let report = ScriptErrorReport::new(
+                            &engine,
+                            *id,
+                            script,
+                            ScriptErrorStage::Compile,
+                            &err,
+                        );
+                        error!("{}", report);
+                        script_errors.report(report);
                     }
-                };
-                program_cache.insert(*id, program);
-                info!("Modified script: {:?}", id);
+                }
             }
             AssetEvent::Removed { id } => {
+                program_cache.remove(id);
+                script_errors.clear_script(*id);
+                let id = *id;
+                commands.add(move |world: &mut World| despawn_owned_ops(world, id));
                 info!("Removed script: {:?}", id);
             }
             AssetEvent::Unused { id } => {
@@ -72,9 +116,42 @@ pub fn load_scripts(
     }
 }
 
+/// Compile a script from where it is on disk, so Steel can resolve the files it requires.
+fn compile(engine: &mut Engine, script: &Script) -> Result<RawProgramWithSymbols, SteelErr> {
+    let path = Path::new(ASSETS_DIR).join(&script.path);
+    engine.emit_raw_program(script.code.clone(), path)
+}
+
 #[derive(Asset, TypePath, Debug)]
 pub struct Script {
+    /// The path of the script, relative to the assets folder.
+    path: PathBuf,
     code: String,
+    /// The `.scm` files the script requires, directly or through other required files.
+    requires: Vec<(PathBuf, String)>,
+}
+
+impl Script {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// The path and code of the script or one of its required files, from a path Steel knows
+    /// the file by, i.e. `assets/lib/noise.scm`.
+    pub fn find_source(&self, path: &Path) -> Option<(&Path, &str)> {
+        std::iter::once((self.path.as_path(), self.code.as_str()))
+            .chain(
+                self.requires
+                    .iter()
+                    .map(|(path, code)| (path.as_path(), code.as_str())),
+            )
+            .filter(|(source, _)| path.ends_with(source))
+            .max_by_key(|(source, _)| source.components().count())
+    }
 }
 
 #[derive(Default)]
@@ -89,6 +166,9 @@ pub enum ScriptLoaderError {
     /// An [std::string::FromUtf8Error]
     #[error("Could not convert bytes to string: {0}")]
     FromUtf8(#[from] std::string::FromUtf8Error),
+    /// A [ReadAssetBytesError] for a required file
+    #[error("Could not load required script: {0}")]
+    Require(#[from] ReadAssetBytesError),
 }
 
 impl AssetLoader for ScriptLoader {
@@ -100,14 +180,34 @@ impl AssetLoader for ScriptLoader {
         &'a self,
         reader: &'a mut Reader,
         _settings: &'a Self::Settings,
-        _load_context: &'a mut LoadContext,
+        load_context: &'a mut LoadContext,
     ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
         Box::pin(async move {
             let mut bytes = Vec::new();
             reader.read_to_end(&mut bytes).await?;
             let code = String::from_utf8(bytes)?;
             info!("Loaded script: {}", code);
-            Ok(Script { code })
+
+            // Steel reads required files itself, but reading them here as well makes the asset
+            // server reload the script when one of them changes
+            let path = load_context.path().to_path_buf();
+            let mut requires: Vec<(PathBuf, String)> = vec![];
+            let mut pending = required_paths(&path, &code);
+            while let Some(required) = pending.pop() {
+                if required == path || requires.iter().any(|(other, _)| other == &required) {
+                    continue;
+                }
+                let bytes = load_context.read_asset_bytes(required.clone()).await?;
+                let required_code = String::from_utf8(bytes)?;
+                pending.extend(required_paths(&required, &required_code));
+                requires.push((required, required_code));
+            }
+
+            Ok(Script {
+                path,
+                code,
+                requires,
+            })
         })
     }
 
@@ -115,3 +215,97 @@ impl AssetLoader for ScriptLoader {
         &["scm"]
     }
 }
+
+/// The `.scm` files a script requires, i.e. `(require "lib.scm")`, relative to the assets
+/// folder. Other requires, such as Steel's own modules, are left to Steel.
+fn required_paths(script: &Path, code: &str) -> Vec<PathBuf> {
+    let code = strip_comments(code);
+    let dir = script.parent().unwrap_or(Path::new(""));
+
+    let mut paths = vec![];
+    for (start, _) in code.match_indices("(require") {
+        let form = &code[start + "(require".len()..];
+        if !form.starts_with(char::is_whitespace) {
+            continue;
+        }
+
+        // Collect the string literals up to the end of the form
+        let mut depth = 1;
+        let mut literal: Option<String> = None;
+        let mut chars = form.chars();
+        while let Some(c) = chars.next() {
+            if let Some(s) = &mut literal {
+                match c {
+                    '\\' => s.extend(chars.next()),
+                    '"' => {
+                        if s.ends_with(".scm") {
+                            paths.push(normalize(&dir.join(&*s)));
+                        }
+                        literal = None;
+                    }
+                    c => s.push(c),
+                }
+                continue;
+            }
+
+            match c {
+                '"' => literal = Some(String::new()),
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    paths
+}
+
+/// Remove `;` comments, leaving string literals as they are.
+fn strip_comments(code: &str) -> String {
+    let mut stripped = String::with_capacity(code.len());
+    let mut in_string = false;
+    let mut in_comment = false;
+    let mut chars = code.chars();
+    while let Some(c) = chars.next() {
+        if in_comment {
+            if c == '\n' {
+                in_comment = false;
+                stripped.push(c);
+            }
+            continue;
+        }
+
+        match c {
+            ';' if !in_string => in_comment = true,
+            '"' => {
+                in_string = !in_string;
+                stripped.push(c);
+            }
+            '\\' if in_string => {
+                stripped.push(c);
+                stripped.extend(chars.next());
+            }
+            c => stripped.push(c),
+        }
+    }
+    stripped
+}
+
+/// Resolve `..` and `.` in a path, since asset paths must not contain them.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
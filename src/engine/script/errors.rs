@@ -0,0 +1,157 @@
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use steel::rerrs::SteelErr;
+use steel::steel_vm::engine::Engine;
+
+use crate::engine::script::asset::Script;
+
+/// Whether a script failed to compile or failed while running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptErrorStage {
+    Compile,
+    Runtime,
+}
+
+impl ScriptErrorStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScriptErrorStage::Compile => "Compile",
+            ScriptErrorStage::Runtime => "Runtime",
+        }
+    }
+}
+
+/// An error from a script, with where it happened in the script when Steel knows.
+#[derive(Debug, Clone)]
+pub struct ScriptErrorReport {
+    pub script: AssetId<Script>,
+    /// The path of the script, relative to the assets folder.
+    pub path: PathBuf,
+    pub stage: ScriptErrorStage,
+    pub message: String,
+    /// The line and column, counting from 1.
+    pub location: Option<(usize, usize)>,
+    /// How many times the error happened, i.e. once a frame while a script keeps failing.
+    pub count: u32,
+}
+
+impl ScriptErrorReport {
+    pub fn new(
+        engine: &Engine,
+        script: AssetId<Script>,
+        source: &Script,
+        stage: ScriptErrorStage,
+        err: &SteelErr,
+    ) -> Self {
+        let mut path = source.path().to_path_buf();
+        let mut location = None;
+        // The span may be in a file the script requires. Without knowing which, the offset
+        // can't be trusted, so the location is left out.
+        if let Some(span) = err.span() {
+            let file = span
+                .source_id()
+                .and_then(|id| engine.get_path_for_source_id(&id))
+                .and_then(|file| source.find_source(&file));
+            if let Some((file, code)) = file {
+                path = file.to_path_buf();
+                location = self::location(code, span.start());
+            }
+        }
+
+        Self {
+            script,
+            path,
+            stage,
+            message: err.to_string(),
+            location,
+            count: 1,
+        }
+    }
+
+    /// Where the error happened, i.e. `project.scm:12:5`.
+    pub fn source(&self) -> String {
+        match self.location {
+            Some((line, column)) => format!("{}:{}:{}", self.path.display(), line, column),
+            None => self.path.display().to_string(),
+        }
+    }
+
+    fn is_same(&self, other: &ScriptErrorReport) -> bool {
+        self.script == other.script
+            && self.stage == other.stage
+            && self.location == other.location
+            && self.message == other.message
+    }
+}
+
+impl Display for ScriptErrorReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} error in {}: {}",
+            self.stage.as_str(),
+            self.source(),
+            self.message
+        )
+    }
+}
+
+/// The line and column of a byte offset into a script.
+fn location(code: &str, offset: usize) -> Option<(usize, usize)> {
+    let before = code.get(..offset)?;
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .map_or(0, |line| line.chars().count())
+        + 1;
+    Some((line, column))
+}
+
+/// The errors scripts have reported, in the order they first happened. An error that keeps
+/// happening is counted rather than reported again.
+#[derive(Resource, Default, Debug)]
+pub struct ScriptErrors(Vec<ScriptErrorReport>);
+
+impl ScriptErrors {
+    /// Record an error. Returns true if it's new, so it's only logged once.
+    pub fn report(&mut self, report: ScriptErrorReport) -> bool {
+        match self.0.iter_mut().find(|other| other.is_same(&report)) {
+            Some(other) => {
+                other.count += 1;
+                false
+            }
+            None => {
+                self.0.push(report);
+                true
+            }
+        }
+    }
+
+    /// Forget the errors of a script, i.e. once it has been reloaded.
+    pub fn clear_script(&mut self, script: AssetId<Script>) {
+        self.0.retain(|report| report.script != script);
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn for_script(&self, script: AssetId<Script>) -> impl Iterator<Item = &ScriptErrorReport> {
+        self.0.iter().filter(move |report| report.script == script)
+    }
+
+    pub fn has_errors(&self, script: AssetId<Script>) -> bool {
+        self.for_script(script).next().is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ScriptErrorReport> {
+        self.0.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
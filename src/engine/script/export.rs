@@ -0,0 +1,242 @@
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::engine::op::{OpBypass, OpInputs, OpName, OpTypeName};
+use crate::engine::param::{
+    ParamDefault, ParamExpression, ParamLink, ParamName, ParamOrder, ParamValue, ScriptedParam,
+};
+use crate::engine::script::asset::Script;
+use crate::engine::script::ScriptTouched;
+use crate::Sets;
+
+pub struct ScriptExportPlugin;
+
+impl Plugin for ScriptExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScriptExportPath>()
+            .add_event::<ExportScript>()
+            .add_systems(Update, hotkeys.in_set(Sets::Ui))
+            // Scripts have touched their ops and params by the time we get here, so we can tell
+            // what the script already covers.
+            .add_systems(
+                Update,
+                export
+                    .run_if(on_event::<ExportScript>())
+                    .in_set(Sets::Params),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportMode {
+    /// Emit every op, param and connection in the graph.
+    Full,
+    /// Emit only ops the script doesn't create and params that were changed outside of the
+    /// script. Connections and bypasses aren't tracked by the script and are always emitted.
+    Diff,
+}
+
+/// Write the current graph out as `op!`, `param!` and `connect!` forms.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ExportScript(pub ExportMode);
+
+/// The path exported scripts are written to.
+#[derive(Resource, Deref, DerefMut, Debug, Clone)]
+pub struct ScriptExportPath(pub PathBuf);
+
+impl Default for ScriptExportPath {
+    fn default() -> Self {
+        Self(PathBuf::from("assets/export.scm"))
+    }
+}
+
+fn hotkeys(keys: Res<ButtonInput<KeyCode>>, mut ev_export: EventWriter<ExportScript>) {
+    let modifier = keys.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ]);
+    if !modifier || !keys.just_pressed(KeyCode::KeyE) {
+        return;
+    }
+
+    if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        ev_export.send(ExportScript(ExportMode::Diff));
+    } else {
+        ev_export.send(ExportScript(ExportMode::Full));
+    }
+}
+
+fn export(
+    mut ev_export: EventReader<ExportScript>,
+    path: Res<ScriptExportPath>,
+    op_q: Query<(
+        &OpName,
+        &OpTypeName,
+        Option<&OpInputs>,
+        Option<&Children>,
+        Has<ScriptTouched>,
+        Has<OpBypass>,
+        Option<&Handle<Script>>,
+    )>,
+    name_q: Query<&OpName>,
+    param_q: Query<(
+        &ParamName,
+        &ParamValue,
+        &ParamOrder,
+        Option<&ParamDefault>,
+        Option<&ParamExpression>,
+        Option<&ParamLink>,
+        Has<ScriptedParam>,
+    )>,
+) {
+    let Some(ExportScript(mode)) = ev_export.read().last().copied() else {
+        return;
+    };
+
+    let mut ops = op_q.iter().collect::<Vec<_>>();
+    ops.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut op_forms = String::new();
+    let mut param_forms = String::new();
+    let mut connections = Vec::new();
+    for (name, type_name, inputs, children, touched, bypass, script) in ops {
+        let ty = type_name.0;
+
+        if mode == ExportMode::Full || !touched {
+            writeln!(op_forms, "(op! '{} {:?})", ty, name.0).unwrap();
+        }
+        // Bypass isn't tracked by the script either, so is emitted in both modes
+        if bypass {
+            writeln!(param_forms, "(bypass! (op {:?}) #t)", name.0).unwrap();
+        }
+        if let Some(path) = script.and_then(|script| script.path()) {
+            writeln!(
+                param_forms,
+                "(op-script! (op {:?}) {:?})",
+                name.0,
+                path.path().to_string_lossy()
+            )
+            .unwrap();
+        }
+
+        let mut params = children
+            .iter()
+            .flat_map(|children| children.iter())
+            .filter_map(|child| param_q.get(*child).ok())
+            .collect::<Vec<_>>();
+        params.sort_by(|a, b| a.2.cmp(b.2).then_with(|| a.0.cmp(b.0)));
+
+        for (param_name, value, _, default, expression, link, scripted) in params {
+            // Links and expressions set from the ui aren't tracked by the script, so are emitted
+            // in both modes
+            if let Some(link) = link {
+                writeln!(
+                    param_forms,
+                    "(link! (op {:?}) {:?} (op {:?}) {:?})",
+                    name.0, param_name.0, link.op, link.param
+                )
+                .unwrap();
+                continue;
+            }
+            if let Some(expression) = expression {
+                writeln!(
+                    param_forms,
+                    "(expr! (op {:?}) {:?} {:?})",
+                    name.0, param_name.0, expression.0
+                )
+                .unwrap();
+                continue;
+            }
+            let unchanged = scripted || default.is_some_and(|default| default.0 == *value);
+            if mode == ExportMode::Diff && unchanged {
+                continue;
+            }
+            let Some(value) = script_value(value, &name_q) else {
+                continue;
+            };
+            writeln!(
+                param_forms,
+                "(param! (op {:?}) {:?} {})",
+                name.0, param_name.0, value
+            )
+            .unwrap();
+        }
+
+        if let Some(inputs) = inputs {
+            for (input_port, (output, output_port)) in inputs.connections.iter() {
+                if let Ok(output) = name_q.get(*output) {
+                    connections.push((output.0.clone(), *output_port, name.0.clone(), *input_port));
+                }
+            }
+        }
+    }
+    connections.sort();
+
+    let mut script = op_forms;
+    if !param_forms.is_empty() {
+        script.push('\n');
+        script.push_str(&param_forms);
+    }
+    if !connections.is_empty() {
+        script.push('\n');
+        for (output, output_port, input, input_port) in connections {
+            writeln!(
+                script,
+                "(connect! (op {:?}) {} (op {:?}) {})",
+                output, output_port, input, input_port
+            )
+            .unwrap();
+        }
+    }
+
+    match std::fs::write(&path.0, script) {
+        Ok(()) => info!("Exported {:?} script to {:?}", mode, path.0),
+        Err(err) => error!("Failed to export script: {}", err),
+    }
+}
+
+/// Format a param value as a Steel expression that `update_param` will accept.
+fn script_value(value: &ParamValue, name_q: &Query<&OpName>) -> Option<String> {
+    let op = |entity: &Entity| {
+        name_q
+            .get(*entity)
+            .ok()
+            .map(|name| format!("(op {:?})", name.0))
+    };
+
+    let value = match value {
+        ParamValue::None => return None,
+        ParamValue::F32(x) => format!("{:?}", x),
+        ParamValue::U32(x) => format!("{}", x),
+        ParamValue::UVec2(v) => format!("(list {} {})", v.x, v.y),
+        ParamValue::Vec2(v) => format!("(list {:?} {:?})", v.x, v.y),
+        ParamValue::Vec3(v) => format!("(list {:?} {:?} {:?})", v.x, v.y, v.z),
+        ParamValue::Quat(v) => format!("(list {:?} {:?} {:?} {:?})", v.x, v.y, v.z, v.w),
+        ParamValue::Color(v) | ParamValue::Vec4(v) => {
+            format!("(list {:?} {:?} {:?} {:?})", v.x, v.y, v.z, v.w)
+        }
+        ParamValue::Mat4(m) => {
+            let cols = m.to_cols_array().map(|x| format!("{:?}", x));
+            format!("(list {})", cols.join(" "))
+        }
+        ParamValue::Bool(x) => if *x { "#t" } else { "#f" }.to_string(),
+        ParamValue::I32(x) => format!("{}", x),
+        ParamValue::String(x) => format!("{:?}", x),
+        ParamValue::Path(x) => format!("{:?}", x.to_string_lossy()),
+        // A pulse only lasts a frame, so there's nothing to restore
+        ParamValue::Pulse(_) => return None,
+        ParamValue::TextureOp(x) | ParamValue::MeshOp(x) | ParamValue::MaterialOp(x) => {
+            op(x.as_ref()?)?
+        }
+        ParamValue::CameraOps(x) | ParamValue::LightOps(x) => {
+            let ops = x.iter().filter_map(op).collect::<Vec<_>>();
+            format!("(list {})", ops.join(" "))
+        }
+    };
+
+    Some(value)
+}
@@ -3,6 +3,7 @@ use std::num::NonZeroU8;
 use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
 use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::Duration;
 
 use bevy::app::AppExit;
 use bevy::asset::AssetContainer;
@@ -15,6 +16,7 @@ use rustyline::error::ReadlineError;
 use rustyline::highlight::MatchingBracketHighlighter;
 use rustyline::validate::MatchingBracketValidator;
 use rustyline::{DefaultEditor, Editor};
+use steel::compiler::program::RawProgramWithSymbols;
 use steel::gc::unsafe_erased_pointers::CustomReference;
 use steel::rvals::{CustomType, IntoSteelVal};
 use steel::steel_vm::engine::Engine;
@@ -22,36 +24,40 @@ use steel::steel_vm::register_fn::RegisterFn;
 use steel::SteelVal;
 use steel_derive::Steel;
 
+use crate::engine::animation::{Keyframes, Timeline};
 use crate::engine::graph::event::Connect;
 use crate::engine::graph::GraphState;
-use crate::engine::op::component::types::camera::ComponentOpCamera;
-use crate::engine::op::component::types::geom::ComponentOpGeom;
-use crate::engine::op::component::types::light::ComponentOpLight;
-use crate::engine::op::component::types::window::ComponentOpWindow;
-use crate::engine::op::material::types::standard::MaterialOpStandard;
-use crate::engine::op::mesh::types::cuboid::MeshOpCuboid;
-use crate::engine::op::mesh::types::grid::MeshOpGrid;
-use crate::engine::op::mesh::types::noise::MeshOpNoise;
-use crate::engine::op::mesh::types::plane::MeshOpPlane;
-use crate::engine::op::texture::types::composite::TextureOpComposite;
-use crate::engine::op::texture::types::noise::TextureOpNoise;
-use crate::engine::op::texture::types::ramp::TextureOpRamp;
+use crate::engine::history::{Edit, History};
+use crate::engine::op::registry::OpRegistry;
+use crate::engine::op::stats::OpStats;
 use crate::engine::op::texture::TextureOp;
-use crate::engine::op::{OpCategory, OpName, OpRef, OpType};
-use crate::engine::param::{ParamName, ParamValue, ScriptedParam, ScriptedParamError};
+use crate::engine::op::{
+    despawn_op, spawn_op, OpBypass, OpCategory, OpName, OpOwner, OpRef, OpType,
+};
+use crate::engine::param::{
+    ParamError, ParamExpression, ParamLink, ParamMeta, ParamName, ParamValue, ScriptedParam,
+    ScriptedParamError,
+};
+use crate::engine::preset::{recall_preset, save_preset};
 use crate::engine::script::asset::{ProgramCache, Script, ScriptAssetPlugin};
+use crate::engine::script::errors::{ScriptErrorReport, ScriptErrorStage, ScriptErrors};
+use crate::engine::script::export::ScriptExportPlugin;
 use crate::engine::script::helper::RustylineHelper;
 use crate::index::{CompositeIndex2, UniqueIndex};
 use crate::Sets;
 
-mod asset;
+pub mod asset;
+pub mod errors;
+pub mod export;
 mod helper;
 
 pub struct ScriptPlugin;
 
 impl Plugin for ScriptPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(ScriptAssetPlugin)
+        app.add_plugins((ScriptAssetPlugin, ScriptExportPlugin))
+            .init_resource::<RunningScript>()
+            .init_resource::<ScriptErrors>()
             .add_systems(First, clear_touched)
             .add_systems(Last, (drop_untouched_entity, clear_untouched_params))
             .add_systems(Startup, setup)
@@ -68,10 +74,15 @@ fn clear_touched(mut commands: Commands, touched_q: Query<Entity, With<ScriptTou
 fn drop_untouched_entity(
     mut commands: Commands,
     mut index: ResMut<UniqueIndex<OpName>>,
-    touched_q: Query<(Entity, &OpName), Without<ScriptTouched>>,
+    touched_q: Query<(Entity, &OpName, &OpOwner), Without<ScriptTouched>>,
     op_ref_q: Query<(Entity, &OpRef), With<OpRef>>,
 ) {
-    for (entity, op_name) in touched_q.iter() {
+    for (entity, op_name, owner) in touched_q.iter() {
+        // Ops from the repl and the editor live until they're deleted
+        if !matches!(owner, OpOwner::Script(_)) {
+            continue;
+        }
+
         commands.entity(entity).despawn_recursive();
         // commands.entity(**ui_ref).despawn_recursive();
         for (entity, op_ref) in op_ref_q.iter() {
@@ -99,6 +110,24 @@ fn clear_untouched_params(
 #[derive(Component)]
 struct ScriptTouched;
 
+/// The script being run, which owns the ops it creates. Ops created outside of a script, i.e.
+/// from the repl, are owned by the repl.
+#[derive(Resource, Default, Debug)]
+struct RunningScript(Option<AssetId<Script>>);
+
+/// Despawn the ops owned by a script.
+pub(crate) fn despawn_owned_ops(world: &mut World, script: AssetId<Script>) {
+    let mut owned_q = world.query::<(Entity, &OpOwner)>();
+    let owned = owned_q
+        .iter(world)
+        .filter(|(_, owner)| **owner == OpOwner::Script(script))
+        .map(|(entity, _)| entity)
+        .collect::<Vec<_>>();
+    for entity in owned {
+        despawn_op(world, entity);
+    }
+}
+
 #[derive(Deref, DerefMut)]
 struct ReadLineEditor(Receiver<String>);
 
@@ -143,6 +172,10 @@ impl Default for ReadLineEditor {
 #[derive(Default, Deref, DerefMut)]
 struct LispEngine(Rc<RefCell<Engine>>);
 
+/// Compiled param expressions, along with the source they were compiled from.
+#[derive(Default, Deref, DerefMut)]
+struct ExpressionCache(HashMap<Entity, (String, Result<RawProgramWithSymbols, String>)>);
+
 #[derive(Debug, Deref, DerefMut, Steel, PartialEq, Clone)]
 pub struct EntityRef(Entity);
 
@@ -157,10 +190,12 @@ fn setup(world: &mut World) {
     let mut engine = Engine::new();
     engine.register_value("*world*", SteelVal::Void);
     engine.register_value("*time*", SteelVal::Void);
+    engine.register_value("*self*", SteelVal::Void);
     let editor = ReadLineEditor::default();
     let engine = Rc::new(RefCell::new(engine));
     world.insert_non_send_resource(editor);
     world.insert_non_send_resource(LispEngine(engine));
+    world.init_non_send_resource::<ExpressionCache>();
     let curr_time = world.resource::<Time<Virtual>>().elapsed_seconds();
     let world_cell = world.as_unsafe_world_cell();
     unsafe {
@@ -183,17 +218,33 @@ fn setup(world: &mut World) {
                     .register_fn("-op!", op_bang)
                     .register_fn("-param", param)
                     .register_fn("-param!", param_bang)
+                    .register_fn("-expr", expr)
+                    .register_fn("-expr!", expr_bang)
+                    .register_fn("-link!", link_bang)
+                    .register_fn("-keyframes", keyframes)
+                    .register_fn("-timeline-time", timeline_time)
+                    .register_fn("-unlink!", unlink_bang)
                     .register_fn("-connect!", connect_bang)
+                    .register_fn("-bypass!", bypass_bang)
+                    .register_fn("-op-script!", op_script_bang)
+                    .register_fn("-persist!", persist_bang)
+                    .register_fn("-preset-save!", preset_save_bang)
+                    .register_fn("-preset-recall!", preset_recall_bang)
+                    .register_fn("-op-stats", op_stats)
+                    .register_fn("-op-types", op_types)
                     .register_fn("rand", rand);
                 let prog = engine
                     .emit_raw_program_no_path(
                         r#"
-                        ; get an op
+                        ; get an op by its path, i.e. "/scene/noise1"
                         (define (op name)
                             (-op *world* name))
                         ; create an op
                         (define (op! type name)
                             (-op! *world* type name))
+                        ; list the types op! can create
+                        (define (op-types)
+                            (-op-types *world*))
                         ; get a param
                         (define (param entity name)
                             (when entity
@@ -202,9 +253,60 @@ fn setup(world: &mut World) {
                         (define (param! entity name val)
                             (when entity
                                 (-param! *world* entity name val)))
+                        ; get the expression of a param
+                        (define (expr entity name)
+                            (when entity
+                                (-expr *world* entity name)))
+                        ; set an expression evaluated every frame for a param, or remove it
+                        ; with ""
+                        (define (expr! entity name source)
+                            (when entity
+                                (-expr! *world* entity name source)))
+                        ; get the keyframes of a param, as a list of (time value)
+                        (define (keyframes entity name)
+                            (when entity
+                                (-keyframes *world* entity name)))
+                        ; get the time of the timeline's playhead
+                        (define (timeline-time)
+                            (-timeline-time *world*))
+                        ; make a param follow another op's param
+                        (define (link! entity name source source-name)
+                            (when (and entity source)
+                                (-link! *world* entity name source source-name)))
+                        ; stop a param following another op's param
+                        (define (unlink! entity name)
+                            (when entity
+                                (-unlink! *world* entity name)))
                         ; connect two ops
                         (define (connect! output output-port input input-port)
                             (-connect! *world* output output-port input input-port))
+                        ; bypass an op
+                        (define (bypass! entity bypass)
+                            (when entity
+                                (-bypass! *world* entity bypass)))
+                        ; hand an op over to the project, so it's kept when the script stops
+                        ; creating it and is saved with the project
+                        (define (persist! entity)
+                            (when entity
+                                (-persist! *world* entity)))
+                        ; run a script as the behaviour of an op, with the op bound to *self*,
+                        ; or remove it with ""
+                        (define (op-script! entity path)
+                            (when entity
+                                (-op-script! *world* entity path)))
+                        ; capture the params of an op, or a container and the ops inside it,
+                        ; as a named preset
+                        (define (preset-save! name entity)
+                            (when entity
+                                (-preset-save! *world* name entity)))
+                        ; recall a preset, blending to it over a number of seconds, or 0 to
+                        ; set it immediately
+                        (define (preset-recall! name seconds)
+                            (-preset-recall! *world* name seconds))
+                        ; get the cook statistics of an op
+                        (define (op-stats entity)
+                            (when entity
+                                (-op-stats *world* entity)))
                     "#,
                     )
                     .unwrap();
@@ -234,21 +336,45 @@ pub fn update(world: &mut World) {
             }
         };
 
+        // Commands from the repl can't be undone, but are kept in the history so it's clear
+        // where they happened
+        if let Some(line) = &line {
+            world_cell
+                .world_mut()
+                .resource_mut::<History>()
+                .record(Edit::Script(line.clone()));
+        }
+
+        // Project scripts run first, then the scripts attached to ops
         let mut scripts = vec![];
         {
-            let mut query = world_cell.world_mut().query::<(&Handle<Script>)>();
+            let mut query = world_cell
+                .world_mut()
+                .query::<(Entity, &Handle<Script>, Has<OpName>)>();
             let programs = world_cell
                 .world()
                 .get_non_send_resource::<ProgramCache>()
                 .unwrap();
-            for x in query.iter(world_cell.world()) {
-                let id = AssetId::from(x);
+            for (entity, handle, is_op) in query.iter(world_cell.world()) {
+                let id = handle.id();
                 let Some(script) = programs.get(&id) else {
                     continue;
                 };
-                scripts.push(script.clone());
+                scripts.push((id, is_op.then_some(entity), script.clone()));
             }
         }
+        scripts.sort_by_key(|(_, op, _)| op.is_some());
+        let expressions = world_cell
+            .world_mut()
+            .query::<(Entity, &ParamExpression)>()
+            .iter(world_cell.world())
+            .filter(|(_, expression)| expression.is_active())
+            .map(|(entity, expression)| (entity, expression.0.clone()))
+            .collect::<Vec<_>>();
+        let mut cache = world_cell
+            .world_mut()
+            .get_non_send_resource_mut::<ExpressionCache>()
+            .unwrap();
         world_cell
             .world_mut()
             .get_non_send_resource_mut::<LispEngine>()
@@ -287,12 +413,40 @@ pub fn update(world: &mut World) {
                     }
                 }
 
-                for program in scripts.drain(..) {
+                for (id, op, program) in scripts.drain(..) {
+                    let this =
+                        op.map_or(SteelVal::Void, |op| EntityRef(op).into_steelval().unwrap());
+                    engine
+                        .update_value("*self*", this)
+                        .expect("TODO: panic message");
+                    world_cell.world_mut().resource_mut::<RunningScript>().0 = Some(id);
                     let res = engine.run_raw_program(program);
                     if let Err(e) = res {
-                        error!("Error: {:?}", e);
+                        // A failing script fails every frame, so only log the first time
+                        let world = world_cell.world_mut();
+                        let Some(script) = world.resource::<Assets<Script>>().get(id) else {
+                            continue;
+                        };
+                        let report = ScriptErrorReport::new(
+                            engine,
+                            id,
+                            script,
+                            ScriptErrorStage::Runtime,
+                            &e,
+                        );
+                        let message = report.to_string();
+                        if world.resource_mut::<ScriptErrors>().report(report) {
+                            error!("{}", message);
+                        }
                     }
                 }
+                world_cell.world_mut().resource_mut::<RunningScript>().0 = None;
+                engine
+                    .update_value("*self*", SteelVal::Void)
+                    .expect("TODO: panic message");
+
+                // Expressions run after scripts, so they can tell which params param! set
+                evaluate_expressions(engine, world_cell.world_mut(), &mut cache, &expressions);
             });
     }
 }
@@ -300,8 +454,9 @@ fn op_bang(world: &mut WorldHolder, ty: String, name: String) -> Option<EntityRe
     let world = unsafe { world.world_mut() };
 
     // if the entity already exists, just touch it
+    let name = OpName::from_path(&name);
     let index = world.get_resource::<UniqueIndex<OpName>>().unwrap();
-    if let Some(entity) = index.get(&OpName(name.clone())) {
+    if let Some(entity) = index.get(&name) {
         let entity_ref = EntityRef(entity.clone());
         if let Some(mut entity) = world.get_entity_mut(*entity) {
             entity.insert(ScriptTouched);
@@ -309,32 +464,52 @@ fn op_bang(world: &mut WorldHolder, ty: String, name: String) -> Option<EntityRe
         return Some(entity_ref);
     }
 
-    let name = OpName(name);
-    let mut entity = match ty.as_str() {
-        "ramp" => world.spawn((name, OpType::<TextureOpRamp>::default())),
-        "composite" => world.spawn((name, OpType::<TextureOpComposite>::default())),
-        "noise" => world.spawn((name, OpType::<TextureOpNoise>::default())),
-        "window" => world.spawn((name, OpType::<ComponentOpWindow>::default())),
-        "cuboid" => world.spawn((name, OpType::<MeshOpCuboid>::default())),
-        "grid" => world.spawn((name, OpType::<MeshOpGrid>::default())),
-        "plane" => world.spawn((name, OpType::<MeshOpPlane>::default())),
-        "standard-material" => world.spawn((name, OpType::<MaterialOpStandard>::default())),
-        "mesh-noise" => world.spawn((name, OpType::<MeshOpNoise>::default())),
-        "light" => world.spawn((name, OpType::<ComponentOpLight>::default())),
-        "camera" => world.spawn((name, OpType::<ComponentOpCamera>::default())),
-        "geom" => world.spawn((name, OpType::<ComponentOpGeom>::default())),
-        _ => return None,
+    let owner = world
+        .resource::<RunningScript>()
+        .0
+        .map_or(OpOwner::Repl, OpOwner::Script);
+    let Some(mut entity) = spawn_op(world, &ty, name) else {
+        return None;
     };
 
-    entity.insert(ScriptTouched);
+    entity.insert((ScriptTouched, owner));
 
     Some(EntityRef(entity.id()))
 }
 
+fn persist_bang(world: &mut WorldHolder, entity: EntityRef) {
+    let world = unsafe { world.world_mut() };
+    let Some(mut op) = world.get_entity_mut(*entity) else {
+        return;
+    };
+    if op.get::<OpOwner>() != Some(&OpOwner::User) {
+        op.insert(OpOwner::User);
+    }
+}
+
+fn op_script_bang(world: &mut WorldHolder, entity: EntityRef, path: String) {
+    let world = unsafe { world.world_mut() };
+
+    let script = (!path.is_empty()).then(|| world.resource::<AssetServer>().load::<Script>(path));
+    let Some(mut op) = world.get_entity_mut(*entity) else {
+        return;
+    };
+    match script {
+        // Scripts run every frame, so only replace a different script
+        Some(script) if op.get::<Handle<Script>>() != Some(&script) => {
+            op.insert(script);
+        }
+        Some(_) => {}
+        None => {
+            op.remove::<Handle<Script>>();
+        }
+    }
+}
+
 fn op(world: &mut WorldHolder, name: String) -> Option<EntityRef> {
     let world = unsafe { world.world() };
     let index = world.get_resource::<UniqueIndex<OpName>>().unwrap();
-    let entity = index.get(&OpName(name));
+    let entity = index.get(&OpName::from_path(&name));
     if let Some(entity) = entity {
         Some(EntityRef(entity.clone()))
     } else {
@@ -356,17 +531,195 @@ fn param_bang(world: &mut WorldHolder, entity: EntityRef, name: String, val: Ste
             .insert(ScriptTouched)
             .insert(ScriptedParam);
 
-        let mut param = world.get_mut::<ParamValue>(entity.clone()).unwrap();
-
-        if let Err(e) = update_param(&mut param, val) {
-            world
-                .entity_mut(entity.clone())
-                .insert(ScriptedParamError(e.to_string()));
+        match set_param_value(world, entity, val) {
+            Ok(()) => {
+                world.entity_mut(entity).remove::<ScriptedParamError>();
+            }
+            Err(e) => {
+                world
+                    .entity_mut(entity)
+                    .insert(ScriptedParamError(e.to_string()));
+            }
         }
     } else {
     }
 }
 
+/// Set a param from a script value. Invalid values are rejected rather than applied.
+fn set_param_value(world: &mut World, entity: Entity, val: SteelVal) -> Result<(), ScriptError> {
+    let meta = world.get::<ParamMeta>(entity).cloned().unwrap_or_default();
+    let mut param = world.get_mut::<ParamValue>(entity).unwrap();
+
+    let mut value = param.clone();
+    update_param(&mut value, val)?;
+    meta.validate(&value)?;
+    *param = value;
+    Ok(())
+}
+
+fn expr_bang(world: &mut WorldHolder, entity: EntityRef, name: String, source: String) {
+    let world = unsafe { world.world_mut() };
+
+    let index = world
+        .get_resource::<CompositeIndex2<OpRef, ParamName>>()
+        .unwrap();
+    let Some(param) = index.get(&(OpRef(*entity), ParamName(name))).copied() else {
+        return;
+    };
+
+    // Scripts set this every frame, so only insert when the source changes
+    let mut param = world.entity_mut(param);
+    if source.is_empty() {
+        param.remove::<ParamExpression>();
+    } else if param
+        .get::<ParamExpression>()
+        .map_or(true, |expression| expression.0 != source)
+    {
+        param.insert(ParamExpression(source));
+    }
+}
+
+fn link_bang(
+    world: &mut WorldHolder,
+    entity: EntityRef,
+    name: String,
+    source: EntityRef,
+    source_name: String,
+) {
+    let world = unsafe { world.world_mut() };
+
+    let Some(op) = world.get::<OpName>(source.0) else {
+        return;
+    };
+    let link = ParamLink {
+        op: op.0.clone(),
+        param: source_name,
+    };
+    let index = world
+        .get_resource::<CompositeIndex2<OpRef, ParamName>>()
+        .unwrap();
+    let Some(param) = index.get(&(OpRef(*entity), ParamName(name))).copied() else {
+        return;
+    };
+
+    // Scripts set this every frame, so only insert when the link changes
+    let mut param = world.entity_mut(param);
+    if param.get::<ParamLink>() != Some(&link) {
+        param.insert(link);
+    }
+}
+
+fn unlink_bang(world: &mut WorldHolder, entity: EntityRef, name: String) {
+    let world = unsafe { world.world_mut() };
+
+    let index = world
+        .get_resource::<CompositeIndex2<OpRef, ParamName>>()
+        .unwrap();
+    if let Some(param) = index.get(&(OpRef(*entity), ParamName(name))).copied() {
+        world.entity_mut(param).remove::<ParamLink>();
+    }
+}
+
+fn keyframes(world: &mut WorldHolder, entity: EntityRef, name: String) -> SteelVal {
+    let world = unsafe { world.world() };
+    let index = world
+        .get_resource::<CompositeIndex2<OpRef, ParamName>>()
+        .unwrap();
+    let Some(keyframes) = index
+        .get(&(OpRef(entity.0), ParamName(name)))
+        .and_then(|entity| world.get::<Keyframes>(*entity))
+    else {
+        return SteelVal::Void;
+    };
+
+    // Each keyframe is a list of its time and value
+    keyframes
+        .iter()
+        .map(|key| vec![SteelVal::from(key.time), SteelVal::from(key.value.clone())])
+        .collect::<Vec<_>>()
+        .into_steelval()
+        .unwrap()
+}
+
+fn timeline_time(world: &mut WorldHolder) -> f32 {
+    let world = unsafe { world.world() };
+    world.resource::<Timeline>().time
+}
+
+fn expr(world: &mut WorldHolder, entity: EntityRef, name: String) -> SteelVal {
+    let world = unsafe { world.world() };
+    let index = world
+        .get_resource::<CompositeIndex2<OpRef, ParamName>>()
+        .unwrap();
+    index
+        .get(&(OpRef(entity.0), ParamName(name)))
+        .and_then(|entity| world.get::<ParamExpression>(*entity))
+        .map_or(SteelVal::Void, |expression| {
+            SteelVal::StringV(expression.0.clone().into())
+        })
+}
+
+/// Evaluate param expressions, compiling them again only when their source changes. The last
+/// value an expression produces is applied to its param, unless a script set it with `param!`,
+/// which is the higher layer.
+fn evaluate_expressions(
+    engine: &mut Engine,
+    world: &mut World,
+    cache: &mut ExpressionCache,
+    expressions: &[(Entity, String)],
+) {
+    // The errors of removed expressions go with them
+    let removed = cache
+        .keys()
+        .filter(|entity| !expressions.iter().any(|(e, _)| e == *entity))
+        .copied()
+        .collect::<Vec<_>>();
+    for entity in removed {
+        cache.remove(&entity);
+        if let Some(mut param) = world.get_entity_mut(entity) {
+            param.remove::<ScriptedParamError>();
+        }
+    }
+
+    for (entity, source) in expressions.iter().cloned() {
+        // Only param! has touched params by now
+        if world.get::<ScriptTouched>(entity).is_some() {
+            continue;
+        }
+
+        let program = match cache.get(&entity) {
+            Some((cached, program)) if *cached == source => program.clone(),
+            _ => {
+                let program = engine
+                    .emit_raw_program_no_path(source.clone())
+                    .map_err(|e| e.to_string());
+                cache.insert(entity, (source, program.clone()));
+                program
+            }
+        };
+
+        let result = program
+            .and_then(|program| engine.run_raw_program(program).map_err(|e| e.to_string()))
+            .and_then(|values| {
+                values
+                    .into_iter()
+                    .last()
+                    .ok_or_else(|| "Expression has no value".to_string())
+            })
+            .and_then(|value| set_param_value(world, entity, value).map_err(|e| e.to_string()));
+
+        let mut param = world.entity_mut(entity);
+        match result {
+            Ok(()) => {
+                param.remove::<ScriptedParamError>();
+            }
+            Err(e) => {
+                param.insert(ScriptedParamError(e));
+            }
+        }
+    }
+}
+
 fn param(world: &mut WorldHolder, entity: EntityRef, name: String) -> SteelVal {
     let world = unsafe { world.world() };
     let index = world
@@ -401,6 +754,75 @@ fn connect_bang(
     SteelVal::Void
 }
 
+fn bypass_bang(world: &mut WorldHolder, entity: EntityRef, bypass: bool) {
+    let world = unsafe { world.world_mut() };
+    let Some(mut entity) = world.get_entity_mut(entity.0) else {
+        return;
+    };
+
+    // Avoid churning change detection when a script sets this every frame
+    match (bypass, entity.contains::<OpBypass>()) {
+        (true, false) => {
+            entity.insert(OpBypass);
+        }
+        (false, true) => {
+            entity.remove::<OpBypass>();
+        }
+        _ => {}
+    }
+}
+
+fn preset_save_bang(world: &mut WorldHolder, name: String, entity: EntityRef) {
+    let world = unsafe { world.world_mut() };
+    let Some(op) = world.get::<OpName>(entity.0).cloned() else {
+        return;
+    };
+    if let Err(err) = save_preset(world, name, &op.0) {
+        warn!("Failed to save preset: {}", err);
+    }
+}
+
+fn preset_recall_bang(world: &mut WorldHolder, name: String, seconds: f32) {
+    let world = unsafe { world.world_mut() };
+    if let Err(err) = recall_preset(world, &name, seconds) {
+        warn!("Failed to recall preset: {}", err);
+    }
+}
+
+fn op_stats(world: &mut WorldHolder, entity: EntityRef) -> SteelVal {
+    let world = unsafe { world.world() };
+    let Some(stats) = world.get::<OpStats>(entity.0) else {
+        return SteelVal::Void;
+    };
+
+    let ms = |duration: Duration| SteelVal::from(duration.as_secs_f64() * 1000.0);
+    let entries = vec![
+        ("cook-count", SteelVal::from(stats.cook_count as f64)),
+        (
+            "last-cook",
+            stats.last_cook.map_or(SteelVal::Void, SteelVal::from),
+        ),
+        ("avg-cook-ms", ms(stats.average_cook_time)),
+        ("avg-update-ms", ms(stats.average_update_time)),
+        ("gpu-ms", stats.gpu_time.map_or(SteelVal::Void, ms)),
+    ];
+    entries
+        .into_iter()
+        .map(|(name, value)| vec![SteelVal::SymbolV(name.into()), value])
+        .collect::<Vec<_>>()
+        .into_steelval()
+        .unwrap()
+}
+
+fn op_types(world: &mut WorldHolder) -> Vec<String> {
+    let world = unsafe { world.world() };
+    world
+        .resource::<OpRegistry>()
+        .iter()
+        .map(|op| op.name.to_string())
+        .collect()
+}
+
 fn rand(min: f32, max: f32) -> f32 {
     let mut rng = rand::thread_rng();
     rng.gen_range(min..max)
@@ -411,6 +833,8 @@ fn rand(min: f32, max: f32) -> f32 {
 pub enum ScriptError {
     #[error("Could not convert value: {0}")]
     Conversion(SteelVal),
+    #[error("Invalid value: {0}")]
+    Param(#[from] ParamError),
 }
 
 fn update_param(param_value: &mut ParamValue, steel_val: SteelVal) -> Result<(), ScriptError> {
@@ -426,227 +850,79 @@ fn update_param(param_value: &mut ParamValue, steel_val: SteelVal) -> Result<(),
             SteelVal::IntV(n) => *p = n as u32,
             _ => return Err(ScriptError::Conversion(steel_val)),
         },
-        ParamValue::Vec2(p) => match steel_val {
-            SteelVal::ListV(ref v) => {
-                let mut iter = v.into_iter();
-                let x = iter.next().unwrap();
-                let y = iter.next().unwrap();
-                match (x, y) {
-                    (SteelVal::NumV(x), SteelVal::NumV(y)) => {
-                        p.x = *x as f32;
-                        p.y = *y as f32;
-                    }
-                    (SteelVal::IntV(x), SteelVal::IntV(y)) => {
-                        p.x = *x as f32;
-                        p.y = *y as f32;
-                    }
-                    _ => return Err(ScriptError::Conversion(steel_val)),
-                }
-            }
+        ParamValue::I32(p) => match steel_val {
+            SteelVal::NumV(n) => *p = n as i32,
+            SteelVal::IntV(n) => *p = n as i32,
             _ => return Err(ScriptError::Conversion(steel_val)),
         },
-        ParamValue::UVec2(p) => match steel_val {
-            SteelVal::ListV(ref v) => {
-                let mut iter = v.into_iter();
-                let x = iter.next().unwrap();
-                let y = iter.next().unwrap();
-                match (x, y) {
-                    (SteelVal::NumV(x), SteelVal::NumV(y)) => {
-                        p.x = *x as u32;
-                        p.y = *y as u32;
-                    }
-                    (SteelVal::IntV(x), SteelVal::IntV(y)) => {
-                        p.x = *x as u32;
-                        p.y = *y as u32;
-                    }
-                    _ => return Err(ScriptError::Conversion(steel_val)),
-                }
-            }
+        ParamValue::String(p) => match steel_val {
+            SteelVal::StringV(s) => *p = s.as_str().to_string(),
+            _ => return Err(ScriptError::Conversion(steel_val)),
+        },
+        ParamValue::Path(p) => match steel_val {
+            SteelVal::StringV(s) => *p = s.as_str().into(),
+            _ => return Err(ScriptError::Conversion(steel_val)),
+        },
+        ParamValue::Pulse(p) => match steel_val {
+            SteelVal::BoolV(b) => *p = b,
             _ => return Err(ScriptError::Conversion(steel_val)),
         },
-        ParamValue::Color(p) => match steel_val {
+        ParamValue::Mat4(p) => match steel_val {
             SteelVal::ListV(ref v) => {
-                let mut iter = v.into_iter();
-                let r = iter.next().unwrap();
-                let g = iter.next().unwrap();
-                let b = iter.next().unwrap();
-                let a = iter.next().unwrap();
-                match (r, g, b, a) {
-                    (
-                        SteelVal::NumV(r),
-                        SteelVal::NumV(g),
-                        SteelVal::NumV(b),
-                        SteelVal::NumV(a),
-                    ) => {
-                        p.x = *r as f32;
-                        p.y = *g as f32;
-                        p.z = *b as f32;
-                        p.w = *a as f32;
-                    }
-                    (
-                        SteelVal::IntV(r),
-                        SteelVal::IntV(g),
-                        SteelVal::IntV(b),
-                        SteelVal::IntV(a),
-                    ) => {
-                        p.x = *r as f32;
-                        p.y = *g as f32;
-                        p.z = *b as f32;
-                        p.w = *a as f32;
-                    }
-                    _ => return Err(ScriptError::Conversion(steel_val)),
-                }
-            }
-            SteelVal::VectorV(ref v) => {
-                let mut iter = v.iter();
-                let r = iter.next().unwrap();
-                let g = iter.next().unwrap();
-                let b = iter.next().unwrap();
-                let a = iter.next().unwrap();
-                match (r, g, b, a) {
-                    (
-                        SteelVal::NumV(r),
-                        SteelVal::NumV(g),
-                        SteelVal::NumV(b),
-                        SteelVal::NumV(a),
-                    ) => {
-                        p.x = *r as f32;
-                        p.y = *g as f32;
-                        p.z = *b as f32;
-                        p.w = *a as f32;
-                    }
-                    (
-                        SteelVal::IntV(r),
-                        SteelVal::IntV(g),
-                        SteelVal::IntV(b),
-                        SteelVal::IntV(a),
-                    ) => {
-                        p.x = *r as f32;
-                        p.y = *g as f32;
-                        p.z = *b as f32;
-                        p.w = *a as f32;
-                    }
+                // Column major, like `Mat4::to_cols_array`
+                let cols = v
+                    .into_iter()
+                    .map(|x| match x {
+                        SteelVal::NumV(x) => Some(*x as f32),
+                        SteelVal::IntV(x) => Some(*x as f32),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>();
+                match cols {
+                    Some(cols) if cols.len() == 16 => *p = Mat4::from_cols_slice(&cols),
                     _ => return Err(ScriptError::Conversion(steel_val)),
                 }
             }
             _ => return Err(ScriptError::Conversion(steel_val)),
         },
+        ParamValue::Vec2(p) => match numbers(&steel_val).as_deref() {
+            Some(&[x, y]) => *p = Vec2::new(x as f32, y as f32),
+            _ => return Err(ScriptError::Conversion(steel_val)),
+        },
+        ParamValue::UVec2(p) => match numbers(&steel_val).as_deref() {
+            Some(&[x, y]) => *p = UVec2::new(x as u32, y as u32),
+            _ => return Err(ScriptError::Conversion(steel_val)),
+        },
+        ParamValue::Color(p) | ParamValue::Vec4(p) => match numbers(&steel_val).as_deref() {
+            Some(&[r, g, b, a]) => *p = Vec4::new(r as f32, g as f32, b as f32, a as f32),
+            _ => return Err(ScriptError::Conversion(steel_val)),
+        },
         ParamValue::Bool(p) => match steel_val {
             SteelVal::BoolV(b) => *p = b,
             _ => return Err(ScriptError::Conversion(steel_val)),
         },
         ParamValue::MeshOp(p) | ParamValue::MaterialOp(p) | ParamValue::TextureOp(p) => {
-            match steel_val {
-                SteelVal::Custom(c) => {
-                    let custom = c.borrow();
-                    let entity = custom.as_any_ref().downcast_ref::<EntityRef>().unwrap();
-                    *p = Some(entity.0.clone());
-                }
-                _ => return Err(ScriptError::Conversion(steel_val)),
+            match op_entity(&steel_val) {
+                Some(entity) => *p = Some(entity),
+                None => return Err(ScriptError::Conversion(steel_val)),
             }
         }
         ParamValue::CameraOps(p) | ParamValue::LightOps(p) => match steel_val {
             SteelVal::ListV(ref v) => {
-                for entity in v {
-                    match entity {
-                        SteelVal::Custom(c) => {
-                            let custom = c.borrow();
-                            let entity = custom.as_any_ref().downcast_ref::<EntityRef>().unwrap();
-                            p.push(entity.0.clone());
-                        }
-                        _ => return Err(ScriptError::Conversion(steel_val)),
-                    }
+                let entities = v.iter().map(op_entity).collect::<Option<Vec<_>>>();
+                match entities {
+                    Some(entities) => *p = entities,
+                    None => return Err(ScriptError::Conversion(steel_val)),
                 }
             }
             _ => return Err(ScriptError::Conversion(steel_val)),
         },
-        ParamValue::Vec3(p) => match steel_val {
-            SteelVal::ListV(ref v) => {
-                let mut iter = v.into_iter();
-                let x = iter.next().unwrap();
-                let y = iter.next().unwrap();
-                let z = iter.next().unwrap();
-                match (x, y, z) {
-                    (SteelVal::NumV(x), SteelVal::NumV(y), SteelVal::NumV(z)) => {
-                        p.x = *x as f32;
-                        p.y = *y as f32;
-                        p.z = *z as f32;
-                    }
-                    (SteelVal::IntV(x), SteelVal::IntV(y), SteelVal::IntV(z)) => {
-                        p.x = *x as f32;
-                        p.y = *y as f32;
-                        p.z = *z as f32;
-                    }
-                    _ => return Err(ScriptError::Conversion(steel_val)),
-                }
-            }
+        ParamValue::Vec3(p) => match numbers(&steel_val).as_deref() {
+            Some(&[x, y, z]) => *p = Vec3::new(x as f32, y as f32, z as f32),
             _ => return Err(ScriptError::Conversion(steel_val)),
         },
-        ParamValue::Quat(p) => match steel_val {
-            SteelVal::ListV(ref v) => {
-                let mut iter = v.into_iter();
-                let x = iter.next().unwrap();
-                let y = iter.next().unwrap();
-                let z = iter.next().unwrap();
-                let w = iter.next().unwrap();
-                match (x, y, z, w) {
-                    (
-                        SteelVal::NumV(x),
-                        SteelVal::NumV(y),
-                        SteelVal::NumV(z),
-                        SteelVal::NumV(w),
-                    ) => {
-                        p.x = *x as f32;
-                        p.y = *y as f32;
-                        p.z = *z as f32;
-                        p.w = *w as f32;
-                    }
-                    (
-                        SteelVal::IntV(x),
-                        SteelVal::IntV(y),
-                        SteelVal::IntV(z),
-                        SteelVal::IntV(w),
-                    ) => {
-                        p.x = *x as f32;
-                        p.y = *y as f32;
-                        p.z = *z as f32;
-                        p.w = *w as f32;
-                    }
-                    _ => return Err(ScriptError::Conversion(steel_val)),
-                }
-            }
-            SteelVal::VectorV(ref v) => {
-                let mut iter = v.iter();
-                let x = iter.next().unwrap();
-                let y = iter.next().unwrap();
-                let z = iter.next().unwrap();
-                let w = iter.next().unwrap();
-                match (x, y, z, w) {
-                    (
-                        SteelVal::NumV(x),
-                        SteelVal::NumV(y),
-                        SteelVal::NumV(z),
-                        SteelVal::NumV(w),
-                    ) => {
-                        p.x = *x as f32;
-                        p.y = *y as f32;
-                        p.z = *z as f32;
-                        p.w = *w as f32;
-                    }
-                    (
-                        SteelVal::IntV(x),
-                        SteelVal::IntV(y),
-                        SteelVal::IntV(z),
-                        SteelVal::IntV(w),
-                    ) => {
-                        p.x = *x as f32;
-                        p.y = *y as f32;
-                        p.z = *z as f32;
-                        p.w = *w as f32;
-                    }
-                    _ => return Err(ScriptError::Conversion(steel_val)),
-                }
-            }
+        ParamValue::Quat(p) => match numbers(&steel_val).as_deref() {
+            Some(&[x, y, z, w]) => *p = Quat::from_xyzw(x as f32, y as f32, z as f32, w as f32),
             _ => return Err(ScriptError::Conversion(steel_val)),
         },
     }
@@ -654,21 +930,51 @@ fn update_param(param_value: &mut ParamValue, steel_val: SteelVal) -> Result<(),
     Ok(())
 }
 
+/// The numbers in a list or vector, which may mix integers and floats.
+fn numbers(steel_val: &SteelVal) -> Option<Vec<f64>> {
+    let number = |x: &SteelVal| match x {
+        SteelVal::NumV(x) => Some(*x),
+        SteelVal::IntV(x) => Some(*x as f64),
+        _ => None,
+    };
+    match steel_val {
+        SteelVal::ListV(v) => v.iter().map(number).collect(),
+        SteelVal::VectorV(v) => v.iter().map(number).collect(),
+        _ => None,
+    }
+}
+
+/// The op a script value refers to, if it's an entity.
+fn op_entity(steel_val: &SteelVal) -> Option<Entity> {
+    match steel_val {
+        SteelVal::Custom(c) => c
+            .borrow()
+            .as_any_ref()
+            .downcast_ref::<EntityRef>()
+            .map(|entity| entity.0),
+        _ => None,
+    }
+}
+
 impl From<ParamValue> for SteelVal {
     fn from(value: ParamValue) -> Self {
         match value {
             ParamValue::None => SteelVal::Void,
             ParamValue::F32(x) => SteelVal::from(x),
             ParamValue::U32(x) => SteelVal::from(x),
-            ParamValue::Color(x) => {
+            ParamValue::Color(x) | ParamValue::Vec4(x) => {
                 let (r, g, b, a) = x.into();
                 vec![r, g, b, a].into_steelval().unwrap()
             }
+            ParamValue::Mat4(x) => x.to_cols_array().to_vec().into_steelval().unwrap(),
             ParamValue::Vec2(v) => {
                 let (x, y) = v.into();
                 vec![x, y].into_steelval().unwrap()
             }
-            ParamValue::Bool(x) => SteelVal::from(x),
+            ParamValue::Bool(x) | ParamValue::Pulse(x) => SteelVal::from(x),
+            ParamValue::I32(x) => SteelVal::IntV(x as isize),
+            ParamValue::String(x) => SteelVal::StringV(x.into()),
+            ParamValue::Path(x) => SteelVal::StringV(x.to_string_lossy().to_string().into()),
             ParamValue::TextureOp(x) | ParamValue::MeshOp(x) | ParamValue::MaterialOp(x) => match x
             {
                 None => SteelVal::Void,
@@ -0,0 +1,241 @@
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use bevy_egui::EguiContexts;
+
+use crate::engine::history::{Edit, History};
+use crate::engine::op::{despawn_op, OpName};
+use crate::engine::project::{
+    restore_connections, restore_op, ConnectionData, OpData, OpSnapshot, ParamDataValue,
+};
+use crate::index::UniqueIndex;
+use crate::ui::graph::{CurrentContainer, SelectedNode};
+use crate::Sets;
+
+/// How far copies are placed from the ops they were copied from.
+const PASTE_OFFSET: Vec2 = Vec2::new(40.0, -40.0);
+
+pub struct ClipboardPlugin;
+
+impl Plugin for ClipboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Clipboard>()
+            .add_systems(Update, edit_selection.in_set(Sets::Ui));
+    }
+}
+
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+// Resources
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+/// Copied ops, along with the connections between them.
+#[derive(Resource, Default, Debug, Clone)]
+pub struct Clipboard {
+    ops: Vec<OpData>,
+    connections: Vec<ConnectionData>,
+}
+
+impl Clipboard {
+    fn copy(entities: impl Iterator<Item = Entity>, snapshot: &OpSnapshot) -> Self {
+        let ops = entities
+            .filter_map(|entity| snapshot.op(entity))
+            .collect::<Vec<_>>();
+        let names = ops
+            .iter()
+            .map(|op| op.name.as_str())
+            .collect::<HashSet<_>>();
+        // Connections to ops outside the copy are dropped, while references and links to them
+        // are kept
+        let connections = snapshot
+            .connections()
+            .into_iter()
+            .filter(|connection| {
+                names.contains(connection.output.as_str())
+                    && names.contains(connection.input.as_str())
+            })
+            .collect();
+
+        Self { ops, connections }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+}
+
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+// Systems
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+fn edit_selection(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut egui_contexts: EguiContexts,
+    selected_q: Query<Entity, With<SelectedNode>>,
+    name_q: Query<&OpName>,
+    op_name_idx: Res<UniqueIndex<OpName>>,
+    snapshot: OpSnapshot,
+    mut clipboard: ResMut<Clipboard>,
+    mut history: ResMut<History>,
+    current: Res<CurrentContainer>,
+) {
+    // Don't edit the graph while typing into a param
+    if egui_contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+
+    let modifier = keys.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ]);
+    let network = current.0.as_ref().map(|container| container.0.clone());
+
+    let selection = || with_contents(selected_q.iter(), &name_q, &op_name_idx);
+
+    if keys.just_pressed(KeyCode::Delete) {
+        let ops = selection();
+        let edits = ops
+            .iter()
+            .filter_map(|entity| {
+                Some(Edit::Delete {
+                    op: snapshot.op(*entity)?,
+                    connections: snapshot.op_connections(*entity),
+                })
+            })
+            .collect::<Vec<_>>();
+        if edits.is_empty() {
+            return;
+        }
+
+        history.record(Edit::Batch(edits));
+        for op in ops.iter() {
+            commands.entity(*op).remove::<SelectedNode>();
+        }
+        commands.add(move |world: &mut World| {
+            for op in ops {
+                despawn_op(world, op);
+            }
+        });
+    } else if modifier && keys.just_pressed(KeyCode::KeyC) {
+        let copied = Clipboard::copy(selection().into_iter(), &snapshot);
+        if !copied.is_empty() {
+            *clipboard = copied;
+        }
+    } else if modifier && keys.just_pressed(KeyCode::KeyD) {
+        let copied = Clipboard::copy(selection().into_iter(), &snapshot);
+        commands.add(move |world: &mut World| paste(world, copied, network));
+    } else if modifier && keys.just_pressed(KeyCode::KeyV) {
+        let copied = clipboard.clone();
+        commands.add(move |world: &mut World| paste(world, copied, network));
+    }
+}
+
+/// The selected ops, along with every op inside the selected containers. Ops belong to a
+/// container by name, so they'd be left behind otherwise.
+fn with_contents(
+    selected: impl Iterator<Item = Entity>,
+    name_q: &Query<&OpName>,
+    op_name_idx: &UniqueIndex<OpName>,
+) -> Vec<Entity> {
+    let mut entities = vec![];
+    for entity in selected {
+        let Ok(name) = name_q.get(entity) else {
+            continue;
+        };
+        let prefix = format!("{}{}", name.0, OpName::SEPARATOR);
+        entities.push(entity);
+        entities.extend(
+            op_name_idx
+                .iter()
+                .filter(|(name, _)| name.0.starts_with(&prefix))
+                .map(|(_, entity)| *entity),
+        );
+    }
+
+    // A container and an op inside it may both be selected
+    let mut seen = HashSet::new();
+    entities.retain(|entity| seen.insert(*entity));
+    entities
+}
+
+/// Spawn copies of the ops in a clipboard in a network. The copies get fresh names, and any
+/// references between them are updated to point at the copies.
+fn paste(world: &mut World, clipboard: Clipboard, network: Option<String>) {
+    if clipboard.is_empty() {
+        return;
+    }
+
+    // Containers are renamed before the ops inside them, which keep their names within the copy
+    let mut ops = clipboard.ops;
+    ops.sort_by_key(|op| op.name.matches(OpName::SEPARATOR).count());
+
+    let index = world.resource::<UniqueIndex<OpName>>();
+    let mut renames = HashMap::new();
+    for op in ops.iter() {
+        let name = OpName(op.name.clone());
+        if let Some(container) = name
+            .network()
+            .and_then(|network| renames.get(network).cloned())
+        {
+            let copy = format!("{}{}{}", container, OpName::SEPARATOR, name.base_name());
+            renames.insert(op.name.clone(), copy);
+            continue;
+        }
+
+        let base = name
+            .base_name()
+            .trim_end_matches(|c: char| c.is_ascii_digit());
+        let base = if base.is_empty() {
+            op.ty.as_str()
+        } else {
+            base
+        };
+        let copy = OpName::unique(network.as_deref(), base, |name| {
+            index.contains_key(name) || renames.values().any(|copy| copy == &name.0)
+        });
+        renames.insert(op.name.clone(), copy.0);
+    }
+
+    let rename = |name: &String| renames.get(name).cloned().unwrap_or(name.clone());
+    let connections = clipboard
+        .connections
+        .iter()
+        .map(|connection| ConnectionData {
+            output: rename(&connection.output),
+            input: rename(&connection.input),
+            ..connection.clone()
+        })
+        .collect::<Vec<_>>();
+
+    let mut edits = vec![];
+    for mut op in ops {
+        op.name = rename(&op.name);
+        op.position = op.position.map(|position| position + PASTE_OFFSET);
+        for param in op.params.iter_mut() {
+            match &mut param.value {
+                ParamDataValue::Op(Some(name)) => *name = rename(name),
+                ParamDataValue::Ops(names) => {
+                    names.iter_mut().for_each(|name| *name = rename(name))
+                }
+                _ => {}
+            }
+            if let Some(link) = param.link.as_mut() {
+                link.op = rename(&link.op);
+            }
+        }
+
+        restore_op(world, op.clone());
+        edits.push(Edit::Create {
+            connections: connections
+                .iter()
+                .filter(|connection| connection.input == op.name)
+                .cloned()
+                .collect(),
+            op,
+        });
+    }
+
+    restore_connections(world, connections);
+    world.resource_mut::<History>().record(Edit::Batch(edits));
+}
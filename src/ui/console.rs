@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::engine::script::errors::ScriptErrors;
+use crate::ui::UiState;
+use crate::Sets;
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, console_ui.in_set(Sets::Ui));
+    }
+}
+
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+// Systems
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+/// List the errors scripts have reported. Errors go away by themselves when their script is
+/// fixed and reloaded.
+fn console_ui(
+    mut egui_contexts: EguiContexts,
+    mut ui_state: ResMut<UiState>,
+    mut script_errors: ResMut<ScriptErrors>,
+) {
+    if script_errors.is_empty() {
+        ui_state.console = None;
+        return;
+    }
+
+    ui_state.console = egui::Window::new("Console")
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::Vec2::new(-10.0, -60.0))
+        .default_width(400.0)
+        .collapsible(true)
+        .show(egui_contexts.ctx_mut(), |ui| {
+            egui::ScrollArea::vertical()
+                .max_height(200.0)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for report in script_errors.iter() {
+                        ui.horizontal_wrapped(|ui| {
+                            ui.monospace(report.source());
+                            ui.colored_label(egui::Color32::RED, report.stage.as_str());
+                            if report.count > 1 {
+                                ui.weak(format!("x{}", report.count));
+                            }
+                        });
+                        ui.colored_label(egui::Color32::RED, &report.message);
+                        ui.separator();
+                    }
+                });
+            if ui.button("Clear").clicked() {
+                script_errors.clear();
+            }
+        })
+        .map(|response| response.response);
+}
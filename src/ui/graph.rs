@@ -3,11 +3,11 @@ use bevy::color::palettes::css::{BLACK, GRAY};
 use bevy::ecs::entity::Entities;
 use bevy::ecs::system::lifetimeless::{Read, SQuery, SResMut, Write};
 use bevy::prelude::*;
-use bevy::render::camera::CameraOutputMode;
 use bevy::render::render_resource::{AsBindGroup, ShaderRef};
 use bevy::sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle};
 use bevy::transform::TransformSystem::TransformPropagate;
 use bevy::utils::{info, HashMap};
+use bevy_egui::EguiContexts;
 use bevy_mod_picking::prelude::*;
 use bevy_mod_picking::PickableBundle;
 use bevy_prototype_lyon::draw::Stroke;
@@ -25,19 +25,34 @@ use rand::{random, Rng};
 
 use crate::engine::graph::event::{ClickNode, Connect, Disconnect};
 use crate::engine::graph::{GraphId, GraphNode, GraphState, Layout};
+use crate::engine::history::{Edit, History};
+use crate::engine::op::container::{ContainerInput, ContainerOutput, ContainerPorts};
+use crate::engine::op::stats::OpStats;
+use crate::engine::op::texture;
 use crate::engine::op::texture::render::TextureOpInputImages;
 use crate::engine::op::texture::TextureOp;
-use crate::engine::op::{OpCategory, OpDefaultImage, OpImage, OpInputs, OpName, OpOutputs, OpRef};
-use crate::engine::param::ParamValue;
+use crate::engine::op::{
+    OpBypass, OpCategory, OpDefaultImage, OpImage, OpInputs, OpName, OpOutputs, OpOwner, OpRef,
+};
+use crate::engine::param::{ParamLink, ParamValue};
+use crate::engine::project::ConnectionData;
+use crate::engine::script::asset::Script;
+use crate::engine::script::errors::ScriptErrors;
+use crate::index::UniqueIndex;
 use crate::ui::grid::InfiniteGridSettings;
 use crate::ui::UiCamera;
 use crate::{engine::graph, Sets};
 
+/// How many levels of heat nodes are tinted with.
+const HEAT_STEPS: f32 = 32.0;
+
 pub struct GraphPlugin;
 
 impl Plugin for GraphPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(Material2dPlugin::<NodeMaterial>::default())
+            .init_resource::<HeatOverlay>()
+            .init_resource::<CurrentContainer>()
             .add_systems(Startup, setup)
             .add_systems(First, (ensure_despawn_nodes, update_op_images))
             .add_systems(
@@ -46,8 +61,16 @@ impl Plugin for GraphPlugin {
                     update_graph.in_set(Sets::Graph),
                     (
                         ui,
-                        update_camera_enabled,
+                        navigate_containers,
+                        toggle_bypass,
+                        update_bypassed_nodes,
+                        toggle_heat_overlay,
+                        update_heat,
+                        update_error_nodes,
                         update_ui_refs,
+                        update_container_node_ports,
+                        update_node_visibility,
+                        apply_node_positions,
                         do_layout,
                         click_node.run_if(on_event::<ClickNode>()),
                         handle_connect.run_if(on_event::<Connect>()),
@@ -59,7 +82,7 @@ impl Plugin for GraphPlugin {
             )
             .add_systems(
                 PostUpdate,
-                (draw_connections, draw_refs).after(TransformPropagate),
+                (draw_connections, draw_refs, draw_links).after(TransformPropagate),
             );
     }
 }
@@ -92,6 +115,8 @@ pub struct OutPort(u8);
 #[derive(Component, Clone)]
 pub struct Connecting;
 
+/// Placed on an input port, pointing at the output port that feeds it. An output port may
+/// feed any number of inputs, but each input only has a single connection.
 #[derive(Component)]
 pub struct ConnectedTo {
     entity: Entity,
@@ -104,13 +129,49 @@ pub struct NodeRoot;
 #[derive(Component, Debug)]
 pub struct OpRefConnection;
 
+/// The wire drawn from an op to the op a param of it is linked to.
+#[derive(Component, Debug)]
+pub struct LinkWire(pub Entity);
+
+/// The size of a node on the grid.
+#[derive(Component, Deref, Copy, Clone, Debug)]
+pub struct NodeSize(pub Vec2);
+
 #[derive(Component, Debug)]
 pub struct DisabledNode;
 
+/// A position to move an op's node to once it has been spawned, i.e. when loading a project.
+#[derive(Component, Deref, DerefMut, Copy, Clone, Debug)]
+pub struct NodePosition(pub Vec2);
+
 // ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
 // Resources
 // ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
 
+/// The container whose network is shown on the grid, or `None` for the root network.
+#[derive(Resource, Default, Debug)]
+pub struct CurrentContainer(pub Option<OpName>);
+
+impl CurrentContainer {
+    /// Whether an op is in the network shown on the grid.
+    pub fn contains(&self, name: &OpName) -> bool {
+        name.network() == self.0.as_ref().map(|container| container.0.as_str())
+    }
+
+    /// Show the network the current container is in.
+    pub fn up(&mut self) {
+        if let Some(container) = self.0.take() {
+            self.0 = container
+                .network()
+                .map(|network| OpName(network.to_string()));
+        }
+    }
+}
+
+/// Whether nodes are tinted by how much time their op costs each frame.
+#[derive(Resource, Deref, DerefMut, Default, Debug)]
+pub struct HeatOverlay(pub bool);
+
 // ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
 // Assets
 // ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
@@ -123,6 +184,11 @@ pub struct NodeMaterial {
     pub category_color: LinearRgba,
     #[uniform(0)]
     pub disabled: u32,
+    #[uniform(0)]
+    pub heat: f32,
+    /// Whether a script the op belongs to or has attached has errors.
+    #[uniform(0)]
+    pub error: u32,
     #[texture(1)]
     #[sampler(2)]
     pub texture: Handle<Image>,
@@ -140,27 +206,37 @@ impl Material2d for NodeMaterial {
 
 fn click_node(
     mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
     mut click_events: EventReader<ClickNode>,
     mut prev_selected: Query<Entity, With<SelectedNode>>,
     mut all_mats: Query<&Handle<NodeMaterial>>,
     clicked_q: Query<(&OpRef, &Handle<NodeMaterial>)>,
     mut materials: ResMut<Assets<NodeMaterial>>,
 ) {
+    // Shift toggles a node in the selection rather than replacing it
+    let extend = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
     for event in click_events.read() {
-        for mat in all_mats.iter_mut() {
-            let mat = materials.get_mut(mat).unwrap();
-            mat.selected = 0;
-        }
+        if !extend {
+            for mat in all_mats.iter_mut() {
+                let mat = materials.get_mut(mat).unwrap();
+                mat.selected = 0;
+            }
 
-        for entity in prev_selected.iter_mut() {
-            commands.entity(entity).remove::<SelectedNode>();
+            for entity in prev_selected.iter_mut() {
+                commands.entity(entity).remove::<SelectedNode>();
+            }
         }
 
         if let Ok(q) = clicked_q.get(**event) {
             let entity = **q.0;
-            commands.entity(entity).insert(SelectedNode);
             let material = materials.get_mut(q.1).unwrap();
-            material.selected = 1;
+            if extend && prev_selected.contains(entity) {
+                commands.entity(entity).remove::<SelectedNode>();
+                material.selected = 0;
+            } else {
+                commands.entity(entity).insert(SelectedNode);
+                material.selected = 1;
+            }
         }
     }
 }
@@ -185,29 +261,36 @@ pub fn ui(
             &OpInputs,
             &OpOutputs,
             &GraphId,
+            Has<ContainerInput>,
+            Has<ContainerOutput>,
         ),
         Added<GraphId>,
     >,
 ) {
-    for (entity, name, category, image, input_config, output_config, graph_id) in op_q.iter() {
+    for (entity, name, category, image, input_config, output_config, graph_id, is_in, is_out) in
+        op_q.iter()
+    {
         let (grid, _) = parent.single_mut();
         let index = ((*graph_id).index() as f32 / 100.0) + 10.0;
         let mut rng = rand::thread_rng();
         let size = images.get(&image.0).unwrap().size().as_vec2();
+        let node_size = size / 4.0;
 
         commands.entity(grid).with_children(|parent| {
             parent
                 .spawn((
                     OpRef(entity.clone()),
                     NodeRoot,
+                    NodeSize(node_size),
                     MaterialMesh2dBundle {
                         mesh: meshes
-                            .add(Mesh::from(Rectangle::new(size.x / 4.0, size.y / 4.0)))
+                            .add(Mesh::from(Rectangle::new(node_size.x, node_size.y)))
                             .into(),
                         material: materials.add(NodeMaterial {
                             selected: 0,
                             category_color: category.to_color().to_linear(),
                             disabled: 0,
+                            heat: 0.0,
                             texture: (**image).clone(),
                         }),
                         transform: Transform::from_translation(Vec3::new(rng.gen::<f32>() * 80.0, rng.gen::<f32>() * 80.0, index)),
@@ -220,12 +303,23 @@ pub fn ui(
                     On::<Pointer<Drag>>::run(
                         |drag: ListenerMut<Pointer<Drag>>,
                          projection: Query<&OrthographicProjection, With<UiCamera>>,
-                         mut transform: Query<&mut Transform, With<OpRef>>| {
-                            if let Ok(mut transform) = transform.get_mut(drag.target) {
+                         mut transform: Query<(&OpRef, &mut Transform)>,
+                         name_q: Query<&OpName>,
+                         mut history: ResMut<History>| {
+                            if let Ok((op_ref, mut transform)) = transform.get_mut(drag.target) {
                                 let projection = projection.single();
+                                let from = transform.translation.xy();
 
                                 transform.translation.x += drag.delta.x * projection.scale;
                                 transform.translation.y -= drag.delta.y * projection.scale;
+
+                                if let Ok(name) = name_q.get(op_ref.0) {
+                                    history.record(Edit::Move {
+                                        op: name.clone(),
+                                        from,
+                                        to: transform.translation.xy(),
+                                    });
+                                }
                             }
                         },
                     ),
@@ -236,7 +330,7 @@ pub fn ui(
                     );
                     parent.spawn(
                         Text2dBundle {
-                            text: Text::from_section(&name.0, TextStyle {
+                            text: Text::from_section(name.base_name(), TextStyle {
                                 font: asset_server.load("fonts/Compagnon-Light.otf"),
                                 font_size: 10.0,
                                 color: Color::WHITE,
@@ -256,33 +350,168 @@ pub fn ui(
                             ..Default::default()
                         }
                     );
-                    let spacing = 40.0;
                     let input_category = input_config.category.as_ref().map(|x| x.0).unwrap_or(category.0);
-                    for i in 0..input_config.count {
-                        let offset_x = -(size.x / 8.0);
-                        let total_height = spacing * ((input_config.count - 1) as f32);
-                        let offset_y = i as f32 * spacing - total_height / 2.0;
-                        spawn_port(&mut meshes, &mut color_materials, parent, InPort(i as u8), PortCategory(input_category), Vec3::new(offset_x, offset_y as f32, -0.002));
+                    // The ports of in and out ops are connected through their container
+                    let input_count = if is_in { 0 } else { input_config.count };
+                    let output_count = if is_out { 0 } else { output_config.count };
+                    // Containers only pass textures through their ports
+                    let output_category = if category.is_container() { texture::CATEGORY } else { category.0 };
+                    for i in 0..input_count {
+                        spawn_port(&mut meshes, &mut color_materials, parent, InPort(i as u8), PortCategory(input_category), port_translation(i, input_count, node_size, false));
                     }
-                    for i in 0..output_config.count {
-                        let offset_x = (size.x / 8.0);
-                        let total_height = spacing * ((output_config.count - 1) as f32);
-                        let offset_y = i as f32 * spacing - total_height / 2.0;
-                        spawn_port(&mut meshes, &mut color_materials, parent, OutPort(i as u8), PortCategory(category.0), Vec3::new(offset_x, offset_y, -0.002));
+                    for i in 0..output_count {
+                        spawn_port(&mut meshes, &mut color_materials, parent, OutPort(i as u8), PortCategory(output_category), port_translation(i, output_count, node_size, true));
                     }
                 });
         });
     }
 }
 
-fn update_camera_enabled(
+fn navigate_containers(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut egui_contexts: EguiContexts,
+    mut current: ResMut<CurrentContainer>,
+    selected_q: Query<(&OpName, &OpCategory), With<SelectedNode>>,
+) {
+    // Don't navigate while typing into a param
+    if egui_contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::Enter) {
+        if let Ok((name, category)) = selected_q.get_single() {
+            if category.is_container() {
+                current.0 = Some(name.clone());
+            }
+        }
+    } else if keys.just_pressed(KeyCode::Backspace) {
+        current.up();
+    }
+}
+
+fn update_node_visibility(
+    current: Res<CurrentContainer>,
+    op_q: Query<(&OpName, &UiRef)>,
+    mut node_q: Query<&mut Visibility, With<NodeRoot>>,
+) {
+    for (name, ui_ref) in op_q.iter() {
+        let Ok(mut visibility) = node_q.get_mut(ui_ref.0) else {
+            continue;
+        };
+        let new_visibility = if current.contains(name) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != new_visibility {
+            *visibility = new_visibility;
+        }
+    }
+}
+
+/// Containers gain and lose ports as their in and out ops change.
+fn update_container_node_ports(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+    op_q: Query<
+        (&UiRef, &OpInputs, &OpOutputs),
+        (
+            With<ContainerPorts>,
+            Or<(Changed<OpInputs>, Changed<OpOutputs>, Added<UiRef>)>,
+        ),
+    >,
+    node_q: Query<(&NodeSize, &Children), With<NodeRoot>>,
+    in_port_q: Query<&InPort>,
+    out_port_q: Query<&OutPort>,
+    mut transform_q: Query<&mut Transform, With<Port>>,
+) {
+    for (ui_ref, inputs, outputs) in op_q.iter() {
+        let Ok((node_size, children)) = node_q.get(ui_ref.0) else {
+            continue;
+        };
+        let in_ports = children
+            .iter()
+            .filter_map(|child| in_port_q.get(*child).ok().map(|port| (*child, port.0)))
+            .collect::<Vec<_>>();
+        let out_ports = children
+            .iter()
+            .filter_map(|child| out_port_q.get(*child).ok().map(|port| (*child, port.0)))
+            .collect::<Vec<_>>();
+        if in_ports.len() == inputs.count && out_ports.len() == outputs.count {
+            continue;
+        }
+
+        for (ports, count, is_output) in [
+            (&in_ports, inputs.count, false),
+            (&out_ports, outputs.count, true),
+        ] {
+            for (entity, port) in ports.iter() {
+                let port = *port as usize;
+                if port >= count {
+                    commands.entity(*entity).despawn_recursive();
+                } else if let Ok(mut transform) = transform_q.get_mut(*entity) {
+                    transform.translation = port_translation(port, count, **node_size, is_output);
+                }
+            }
+        }
+
+        commands.entity(ui_ref.0).with_children(|parent| {
+            for i in in_ports.len()..inputs.count {
+                spawn_port(
+                    &mut meshes,
+                    &mut color_materials,
+                    parent,
+                    InPort(i as u8),
+                    PortCategory(texture::CATEGORY),
+                    port_translation(i, inputs.count, **node_size, false),
+                );
+            }
+            for i in out_ports.len()..outputs.count {
+                spawn_port(
+                    &mut meshes,
+                    &mut color_materials,
+                    parent,
+                    OutPort(i as u8),
+                    PortCategory(texture::CATEGORY),
+                    port_translation(i, outputs.count, **node_size, true),
+                );
+            }
+        });
+    }
+}
+
+fn toggle_bypass(
     mut commands: Commands,
-    op_q: Query<(&UiRef, &Camera), With<OpName>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut egui_contexts: EguiContexts,
+    selected_q: Query<(Entity, Has<OpBypass>), With<SelectedNode>>,
+) {
+    // Don't bypass while typing into a param
+    if egui_contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+
+    if !keys.just_pressed(KeyCode::KeyB) {
+        return;
+    }
+
+    for (entity, bypass) in selected_q.iter() {
+        if bypass {
+            commands.entity(entity).remove::<OpBypass>();
+        } else {
+            commands.entity(entity).insert(OpBypass);
+        }
+    }
+}
+
+fn update_bypassed_nodes(
+    mut commands: Commands,
+    op_q: Query<(&UiRef, Has<OpBypass>), With<OpName>>,
     material_q: Query<&Handle<NodeMaterial>>,
     mut materials: ResMut<Assets<NodeMaterial>>,
 ) {
-    for (ui_ref, mut camera) in op_q.iter() {
-        let is_disabled = matches!(camera.output_mode, CameraOutputMode::Skip);
+    for (ui_ref, is_disabled) in op_q.iter() {
         let graph_entity = ui_ref.0;
         let material = material_q.get(graph_entity).unwrap();
         let mut material = materials.get_mut(material).unwrap();
@@ -296,6 +525,99 @@ fn update_camera_enabled(
     }
 }
 
+fn toggle_heat_overlay(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut egui_contexts: EguiContexts,
+    mut heat_overlay: ResMut<HeatOverlay>,
+    material_q: Query<&Handle<NodeMaterial>, With<NodeRoot>>,
+    mut materials: ResMut<Assets<NodeMaterial>>,
+) {
+    // Don't toggle the overlay while typing into a param
+    if egui_contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+
+    if !keys.just_pressed(KeyCode::KeyH) {
+        return;
+    }
+
+    heat_overlay.0 = !heat_overlay.0;
+    if !heat_overlay.0 {
+        for handle in material_q.iter() {
+            if materials
+                .get(handle)
+                .is_some_and(|material| material.heat != 0.0)
+            {
+                materials.get_mut(handle).unwrap().heat = 0.0;
+            }
+        }
+    }
+}
+
+fn update_heat(
+    heat_overlay: Res<HeatOverlay>,
+    op_q: Query<(&UiRef, &OpStats)>,
+    material_q: Query<&Handle<NodeMaterial>>,
+    mut materials: ResMut<Assets<NodeMaterial>>,
+) {
+    if !heat_overlay.0 {
+        return;
+    }
+
+    let max_time = op_q
+        .iter()
+        .map(|(_, stats)| stats.total_time())
+        .max()
+        .unwrap_or_default();
+    if max_time.is_zero() {
+        return;
+    }
+
+    for (ui_ref, stats) in op_q.iter() {
+        let Ok(handle) = material_q.get(ui_ref.0) else {
+            continue;
+        };
+        // Rounded, so timing noise doesn't upload every material again every frame
+        let heat = stats.total_time().as_secs_f32() / max_time.as_secs_f32();
+        let heat = (heat * HEAT_STEPS).round() / HEAT_STEPS;
+        if materials
+            .get(handle)
+            .is_some_and(|material| material.heat != heat)
+        {
+            materials.get_mut(handle).unwrap().heat = heat;
+        }
+    }
+}
+
+/// Mark the nodes of ops whose script, i.e. the one that created them or the one attached to
+/// them, has errors.
+fn update_error_nodes(
+    script_errors: Res<ScriptErrors>,
+    op_q: Query<(&UiRef, Option<&OpOwner>, Option<&Handle<Script>>), With<OpName>>,
+    material_q: Query<&Handle<NodeMaterial>>,
+    mut materials: ResMut<Assets<NodeMaterial>>,
+) {
+    for (ui_ref, owner, script) in op_q.iter() {
+        let owner_errors = match owner {
+            Some(OpOwner::Script(id)) => script_errors.has_errors(*id),
+            _ => false,
+        };
+        let attached_errors = script.is_some_and(|script| script_errors.has_errors(script.id()));
+        let error = (owner_errors || attached_errors) as u32;
+
+        let Ok(handle) = material_q.get(ui_ref.0) else {
+            continue;
+        };
+        // Only touch the material when it changes, so it isn't uploaded again every frame
+        if materials
+            .get(handle)
+            .is_some_and(|material| material.error != error)
+        {
+            materials.get_mut(handle).unwrap().error = error;
+        }
+    }
+}
+
 pub fn update_ui_refs(
     mut commands: Commands,
     mut op_ref_q: Query<(Entity, &OpRef), (With<NodeRoot>, Added<OpRef>)>,
@@ -305,6 +627,20 @@ pub fn update_ui_refs(
     }
 }
 
+fn apply_node_positions(
+    mut commands: Commands,
+    op_q: Query<(Entity, &UiRef, &NodePosition)>,
+    mut transform_q: Query<&mut Transform, With<NodeRoot>>,
+) {
+    for (entity, ui_ref, position) in op_q.iter() {
+        if let Ok(mut transform) = transform_q.get_mut(ui_ref.0) {
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+            commands.entity(entity).remove::<NodePosition>();
+        }
+    }
+}
+
 fn update_op_images(
     mut op_q: Query<(&UiRef, &mut OpImage), Changed<OpImage>>,
     mut material_q: Query<&Handle<NodeMaterial>>,
@@ -317,6 +653,19 @@ fn update_op_images(
     }
 }
 
+/// Where a port sits on a node, spread out along its left or right edge.
+fn port_translation(port: usize, count: usize, node_size: Vec2, is_output: bool) -> Vec3 {
+    let spacing = 40.0;
+    let offset_x = if is_output {
+        node_size.x / 2.0
+    } else {
+        -node_size.x / 2.0
+    };
+    let total_height = spacing * count.saturating_sub(1) as f32;
+    let offset_y = port as f32 * spacing - total_height / 2.0;
+    Vec3::new(offset_x, offset_y, -0.002)
+}
+
 fn spawn_port<T: Component>(
     meshes: &mut ResMut<Assets<Mesh>>,
     color_materials: &mut ResMut<Assets<ColorMaterial>>,
@@ -356,7 +705,16 @@ fn connection_drag(
         ),
         With<Connecting>,
     >,
-    port_q: Query<(&GlobalTransform, &PortCategory, Has<InPort>, Has<OutPort>), With<Port>>,
+    port_q: Query<
+        (
+            &GlobalTransform,
+            &PortCategory,
+            &InheritedVisibility,
+            Has<InPort>,
+            Has<OutPort>,
+        ),
+        With<Port>,
+    >,
 ) {
     if let Ok((transform, children, category, is_input, is_output)) = me_q.get_mut(event.target()) {
         assert_ne!(is_input, is_output);
@@ -365,23 +723,7 @@ fn connection_drag(
         match children {
             None => {
                 commands.entity(event.target).with_children(|parent| {
-                    connection_wire = Some(
-                        parent
-                            .spawn((
-                                ConnectionWire,
-                                ShapeBundle {
-                                    spatial: SpatialBundle {
-                                        transform: Transform::from_translation(Vec3::new(
-                                            0.0, 0.0, -5.03,
-                                        )),
-                                        ..default()
-                                    },
-                                    ..default()
-                                },
-                                Pickable::IGNORE,
-                            ))
-                            .id(),
-                    );
+                    connection_wire = Some(parent.spawn(connection_wire()).id());
                 });
             }
             Some(children) => {
@@ -399,8 +741,10 @@ fn connection_drag(
 
         // Snap to
         let mut closest_port = None;
-        for (transform, target_category, target_is_input, target_is_output) in port_q.iter() {
-            if is_input && target_is_input || is_output && target_is_output {
+        for (transform, target_category, visibility, target_is_input, target_is_output) in
+            port_q.iter()
+        {
+            if is_input && target_is_input || is_output && target_is_output || !visibility.get() {
                 continue;
             }
             if target_category != category {
@@ -432,74 +776,61 @@ fn connection_drag_end(
     mut commands: Commands,
     event: Listener<Pointer<DragEnd>>,
     camera_q: Query<(&Camera, &GlobalTransform), With<UiCamera>>,
-    mut me_q: Query<
+    me_q: Query<
         (
-            Entity,
             &Parent,
             &PortCategory,
-            Has<InPort>,
-            Has<OutPort>,
-            Has<ConnectedTo>,
             Option<&InPort>,
             Option<&OutPort>,
-            Option<&ConnectedTo>,
+            Has<ConnectedTo>,
         ),
         With<Connecting>,
     >,
     port_q: Query<
         (
-            Entity,
             &Parent,
             &GlobalTransform,
             &PortCategory,
-            Has<InPort>,
-            Has<OutPort>,
+            &InheritedVisibility,
             Option<&InPort>,
             Option<&OutPort>,
-            Option<&ConnectedTo>,
         ),
         With<Port>,
     >,
-    ui_ref_q: Query<&OpRef>,
-    connection_wire_q: Query<(Entity, &Parent), With<ConnectionWire>>,
+    op_ref_q: Query<&OpRef>,
+    op_q: Query<(&OpName, Option<&OpInputs>)>,
+    mut history: ResMut<History>,
     mut ev_connect: EventWriter<Connect>,
-    mut ev_disconnect: EventWriter<Disconnect>,
 ) {
-    let (
-        from_entity,
-        from_parent,
-        category,
-        is_input,
-        is_output,
-        is_connected,
-        from_maybe_in_port,
-        from_maybe_out_port,
-        from_maybe_connected_to,
-    ) = me_q.get_mut(event.target()).unwrap();
-    assert_ne!(is_input, is_output);
+    let Ok((from_parent, category, from_in_port, from_out_port, is_connected)) =
+        me_q.get(event.target())
+    else {
+        return;
+    };
+    let is_output = from_out_port.is_some();
 
     commands.entity(event.target()).insert(Pickable::default());
     commands.entity(event.target()).remove::<Connecting>();
 
+    // Wires are owned by the input they connect to, so the one we dragged out of an output is
+    // only temporary
+    if is_output {
+        commands.entity(event.target()).despawn_descendants();
+    }
+
     let (camera, camera_transform) = camera_q.single();
     let pointer_world = camera
         .viewport_to_world_2d(camera_transform, event.pointer_location.position)
         .expect("Failed to convert screen center to world coordinates");
 
     let mut closest_port = None;
-    for (
-        entity,
-        parent,
-        transform,
-        target_category,
-        target_is_input,
-        target_is_output,
-        target_maybe_in_port,
-        target_maybe_out_port,
-        target_maybe_connected_to,
-    ) in port_q.iter()
+    for (parent, transform, target_category, visibility, target_in_port, target_out_port) in
+        port_q.iter()
     {
-        if is_input && target_is_input || is_output && target_is_output {
+        if is_output && target_in_port.is_none() || !is_output && target_out_port.is_none() {
+            continue;
+        }
+        if !visibility.get() {
             continue;
         }
         if target_category != category {
@@ -507,124 +838,117 @@ fn connection_drag_end(
         }
 
         if transform.translation().xy().distance(pointer_world) < 40.0 {
-            closest_port = Some((
-                entity,
-                parent,
-                transform,
-                target_maybe_in_port,
-                target_maybe_out_port,
-                target_maybe_connected_to,
-            ));
+            closest_port = Some((parent, target_in_port, target_out_port));
         }
     }
 
-    if let Some((
-        to_entity,
-        to_parent,
-        transform,
-        to_maybe_in_port,
-        to_maybe_out_port,
-        to_maybe_connected_to,
-    )) = closest_port
-    {
-        let start = Vec2::ZERO;
-        let end = camera
-            .viewport_to_world_2d(camera_transform, event.pointer_location.position)
-            .expect("Failed to convert screen center to world coordinates")
-            - transform.translation().xy();
-
-        let from_op_ref = ui_ref_q.get(**from_parent).unwrap();
-        let to_op_ref = ui_ref_q.get(**to_parent).unwrap();
-
-        // Ensure the connection is created on the output side
-        let connection_wire = connection_wire_q
-            .iter()
-            .find(|(_, parent)| parent.get() == from_entity)
-            .map(|(entity, _)| entity)
-            .expect("No connection wire");
-        if is_output {
-            // Disconnect existing connection
-            if let Some(connected_to) = from_maybe_connected_to {
-                // If we are connecting to the same entity, do nothing
-                if connected_to.entity == to_entity {
-                    return;
-                }
-
-                ev_disconnect.send(Disconnect {
-                    output: from_op_ref.0,
-                    input: connected_to.entity,
-                    output_port: from_maybe_out_port.unwrap().0,
-                    input_port: connected_to.port,
-                });
-            }
-
-            draw_connection(&mut commands, &start, &end, connection_wire, is_output);
-            ev_connect.send(Connect {
-                output: from_op_ref.0,
-                input: to_op_ref.0,
-                output_port: from_maybe_out_port.unwrap().0,
-                input_port: to_maybe_in_port.unwrap().0,
-            });
-        } else {
-            // Disconnect existing connection
-            if let Some(connected_to) = to_maybe_connected_to {
-                // If we are connecting to the same entity, do nothing
-                if connected_to.entity == from_entity {
-                    // Remove the connection wire
-                    commands.entity(event.target()).despawn_descendants();
-                    return;
-                }
-
-                ev_disconnect.send(Disconnect {
-                    output: to_op_ref.0,
-                    input: connected_to.entity,
-                    output_port: to_maybe_out_port.unwrap().0,
-                    input_port: connected_to.port,
-                });
-            }
-            draw_connection(&mut commands, &end, &start, connection_wire, is_output);
-            ev_connect.send(Connect {
-                output: to_op_ref.0,
-                input: from_op_ref.0,
-                output_port: to_maybe_out_port.unwrap().0,
-                input_port: from_maybe_in_port.unwrap().0,
-            });
-        }
-    } else {
-        if !is_connected {
+    let Some((to_parent, to_in_port, to_out_port)) = closest_port else {
+        // Dropped on nothing, an existing connection is left alone
+        if !is_output && !is_connected {
             commands.entity(event.target()).despawn_descendants();
         }
+        return;
+    };
+
+    let from_op_ref = op_ref_q.get(**from_parent).unwrap();
+    let to_op_ref = op_ref_q.get(**to_parent).unwrap();
+    let connect = match (from_out_port, to_in_port) {
+        (Some(out_port), Some(in_port)) => Connect {
+            output: from_op_ref.0,
+            input: to_op_ref.0,
+            output_port: out_port.0,
+            input_port: in_port.0,
+        },
+        _ => Connect {
+            output: to_op_ref.0,
+            input: from_op_ref.0,
+            output_port: to_out_port.unwrap().0,
+            input_port: from_in_port.unwrap().0,
+        },
+    };
+
+    let connection_data = |output: Entity, output_port: u8| {
+        Some(ConnectionData {
+            output: op_q.get(output).ok()?.0 .0.clone(),
+            output_port,
+            input: op_q.get(connect.input).ok()?.0 .0.clone(),
+            input_port: connect.input_port,
+        })
+    };
+    let replaced = op_q
+        .get(connect.input)
+        .ok()
+        .and_then(|(_, inputs)| inputs?.connections.get(&connect.input_port).copied());
+    if replaced != Some((connect.output, connect.output_port)) {
+        if let Some(connection) = connection_data(connect.output, connect.output_port) {
+            history.record(Edit::Connect {
+                connection,
+                replaced: replaced.and_then(|(output, port)| connection_data(output, port)),
+            });
+        }
     }
+
+    // Any previous connection to the input is replaced by the op
+    ev_connect.send(connect);
+}
+
+/// Find the port on an op's node with the given port number.
+fn find_port(
+    op: Entity,
+    port: u8,
+    ui_ref_q: &Query<&UiRef>,
+    children_q: &Query<&Children>,
+    port_number: impl Fn(Entity) -> Option<u8>,
+) -> Option<Entity> {
+    let ui_ref = ui_ref_q.get(op).ok()?;
+    children_q
+        .get(ui_ref.0)
+        .ok()?
+        .iter()
+        .copied()
+        .find(|child| port_number(*child) == Some(port))
 }
 
 fn handle_connect(
     mut commands: Commands,
     ui_ref_q: Query<&UiRef>,
     children_q: Query<&Children>,
-    out_port_q: Query<(Entity, &OutPort)>,
-    in_port_q: Query<(Entity, &InPort)>,
+    out_port_q: Query<&OutPort>,
+    in_port_q: Query<&InPort>,
+    wire_q: Query<(), With<ConnectionWire>>,
     mut ev_connect: EventReader<Connect>,
 ) {
     for connect in ev_connect.read() {
-        let ui_ref = ui_ref_q.get(connect.output).unwrap();
-        let children = children_q.get(ui_ref.0).unwrap();
-        for child in children {
-            if let Ok((out_entity, out_port)) = out_port_q.get(*child) {
-                if out_port.0 == connect.output_port {
-                    let ui_ref = ui_ref_q.get(connect.input).unwrap();
-                    let children = children_q.get(ui_ref.0).unwrap();
-                    for child in children {
-                        if let Ok((in_entity, in_port)) = in_port_q.get(*child) {
-                            if in_port.0 == connect.input_port {
-                                commands.entity(out_entity).insert(ConnectedTo {
-                                    entity: in_entity,
-                                    port: in_port.0,
-                                });
-                            }
-                        }
-                    }
-                }
-            }
+        let out_port = find_port(
+            connect.output,
+            connect.output_port,
+            &ui_ref_q,
+            &children_q,
+            |entity| out_port_q.get(entity).ok().map(|port| port.0),
+        );
+        let in_port = find_port(
+            connect.input,
+            connect.input_port,
+            &ui_ref_q,
+            &children_q,
+            |entity| in_port_q.get(entity).ok().map(|port| port.0),
+        );
+        let (Some(out_entity), Some(in_entity)) = (out_port, in_port) else {
+            continue;
+        };
+
+        commands.entity(in_entity).insert(ConnectedTo {
+            entity: out_entity,
+            port: connect.output_port,
+        });
+
+        let has_wire = children_q
+            .get(in_entity)
+            .is_ok_and(|children| children.iter().any(|child| wire_q.contains(*child)));
+        if !has_wire {
+            commands.entity(in_entity).with_children(|parent| {
+                parent.spawn(connection_wire());
+            });
         }
     }
 }
@@ -633,25 +957,57 @@ fn handle_disconnect(
     mut commands: Commands,
     ui_ref_q: Query<&UiRef>,
     children_q: Query<&Children>,
-    port_q: Query<(Entity, &OutPort)>,
+    out_port_q: Query<&OutPort>,
+    in_port_q: Query<&InPort>,
+    connected_q: Query<&ConnectedTo>,
     mut ev_disconnect: EventReader<Disconnect>,
 ) {
     for disconnect in ev_disconnect.read() {
-        let ui_ref = ui_ref_q.get(disconnect.output).unwrap();
-        let children = children_q.get(ui_ref.0).unwrap();
-        for child in children {
-            if let Ok((entity, out_port)) = port_q.get(*child) {
-                if out_port.0 == disconnect.output_port {
-                    commands
-                        .entity(entity)
-                        .remove::<ConnectedTo>()
-                        .despawn_descendants();
-                }
-            }
+        let out_port = find_port(
+            disconnect.output,
+            disconnect.output_port,
+            &ui_ref_q,
+            &children_q,
+            |entity| out_port_q.get(entity).ok().map(|port| port.0),
+        );
+        let in_port = find_port(
+            disconnect.input,
+            disconnect.input_port,
+            &ui_ref_q,
+            &children_q,
+            |entity| in_port_q.get(entity).ok().map(|port| port.0),
+        );
+        let (Some(out_entity), Some(in_entity)) = (out_port, in_port) else {
+            continue;
+        };
+
+        // The input may have already been connected to another output
+        if connected_q
+            .get(in_entity)
+            .is_ok_and(|connected_to| connected_to.entity == out_entity)
+        {
+            commands
+                .entity(in_entity)
+                .remove::<ConnectedTo>()
+                .despawn_descendants();
         }
     }
 }
 
+fn connection_wire() -> impl Bundle {
+    (
+        ConnectionWire,
+        ShapeBundle {
+            spatial: SpatialBundle {
+                transform: Transform::from_translation(Vec3::new(0.0, 0.0, -5.03)),
+                ..default()
+            },
+            ..default()
+        },
+        Pickable::IGNORE,
+    )
+}
+
 fn draw_connection(
     commands: &mut Commands,
     start: &Vec2,
@@ -727,6 +1083,64 @@ fn draw_refs(
     }
 }
 
+fn draw_links(
+    mut commands: Commands,
+    links_q: Query<(Entity, &ParamLink, &OpRef)>,
+    op_name_idx: Res<UniqueIndex<OpName>>,
+    ui_ref_q: Query<&UiRef>,
+    transform_q: Query<&GlobalTransform>,
+    wire_q: Query<(Entity, &LinkWire)>,
+) {
+    let mut wires = wire_q
+        .iter()
+        .map(|(wire, link_wire)| (link_wire.0, wire))
+        .collect::<HashMap<_, _>>();
+
+    for (param, link, op_ref) in links_q.iter() {
+        let Some(source) = op_name_idx.get(&OpName(link.op.clone())) else {
+            continue;
+        };
+        let (Ok(from_ui_ref), Ok(to_ui_ref)) = (ui_ref_q.get(op_ref.0), ui_ref_q.get(*source))
+        else {
+            continue;
+        };
+        let (Ok(from_transform), Ok(to_transform)) =
+            (transform_q.get(from_ui_ref.0), transform_q.get(to_ui_ref.0))
+        else {
+            continue;
+        };
+
+        let start = Vec2::ZERO;
+        let end = to_transform.translation().xy() - from_transform.translation().xy();
+        let wire = (
+            ShapeBundle {
+                spatial: SpatialBundle {
+                    transform: Transform::from_translation(Vec3::new(0.0, 0.0, -0.4)),
+                    ..default()
+                },
+                path: GeometryBuilder::build_as(&Line(start, end)),
+                ..default()
+            },
+            Stroke::new(Color::from(GRAY), 1.5),
+        );
+        match wires.remove(&param) {
+            Some(entity) => {
+                commands.entity(entity).insert(wire);
+            }
+            None => {
+                commands
+                    .spawn((LinkWire(param), wire))
+                    .set_parent(from_ui_ref.0);
+            }
+        }
+    }
+
+    // Params that were unlinked
+    for wire in wires.into_values() {
+        commands.entity(wire).despawn_recursive();
+    }
+}
+
 fn despawn_connections(
     mut commands: Commands,
     port_children_q: Query<&Children, (With<Port>, Without<Connecting>)>,
@@ -740,19 +1154,24 @@ fn despawn_connections(
 
 fn draw_connections(
     mut commands: Commands,
-    out_port_q: Query<
-        (Entity, &GlobalTransform, &ConnectedTo, &Children),
-        (With<OutPort>, Without<Connecting>),
+    in_port_q: Query<
+        (&GlobalTransform, &ConnectedTo, &Children),
+        (With<InPort>, Without<Connecting>),
     >,
-    in_port_q: Query<(&GlobalTransform), With<InPort>>,
+    out_port_q: Query<&GlobalTransform, With<OutPort>>,
+    wire_q: Query<(), With<ConnectionWire>>,
 ) {
-    // Connect outputs to  inputs
-    for (out_entity, out_transform, out_connected_to, children) in out_port_q.iter() {
-        let (in_transform) = in_port_q.get(out_connected_to.entity).unwrap();
+    // Connect inputs to their outputs
+    for (in_transform, connected_to, children) in in_port_q.iter() {
+        let Ok(out_transform) = out_port_q.get(connected_to.entity) else {
+            continue;
+        };
+        let Some(connection_wire) = children.iter().find(|child| wire_q.contains(**child)) else {
+            continue;
+        };
         let start = Vec2::ZERO;
-        let end = in_transform.translation().xy() - out_transform.translation().xy();
-        let connection_wire = children.first().unwrap();
-        draw_connection(&mut commands, &start, &end, *connection_wire, true);
+        let end = out_transform.translation().xy() - in_transform.translation().xy();
+        draw_connection(&mut commands, &start, &end, *connection_wire, false);
     }
 }
 
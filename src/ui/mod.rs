@@ -1,9 +1,11 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
 use std::fmt::format;
+use std::path::{Path, PathBuf};
 
 use bevy::core::FrameCount;
 use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
 use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
 use bevy_egui::{egui, EguiContexts, EguiPlugin};
 use bevy_mod_picking::DefaultPickingPlugins;
 use bevy_prototype_lyon::plugin::ShapePlugin;
@@ -13,21 +15,39 @@ use steel_parser::ast::IteratorExtensions;
 
 use camera::CameraControllerPlugin;
 
+use crate::engine::animation::{Interpolation, Keyframe, Keyframes, Timeline};
 use crate::engine::graph::event::ClickNode;
+use crate::engine::graph::GraphState;
+use crate::engine::history::{Edit, History, Redo, Undo};
 use crate::engine::op::component::types::camera::ComponentOpCamera;
 use crate::engine::op::component::types::light::ComponentOpLight;
+use crate::engine::op::registry::OpRegistry;
+use crate::engine::op::stats::OpStats;
 use crate::engine::op::texture::TextureOp;
 use crate::engine::op::OpName;
-use crate::engine::op::{OpCategory, OpType, OpTypeName};
-use crate::engine::param::{ParamName, ParamValue, ScriptedParam, ScriptedParamError};
-use crate::index::{Index, IndexPlugin, UniqueIndex};
-use crate::ui::graph::{GraphPlugin, SelectedNode};
-use crate::ui::grid::InfiniteGridPlugin;
+use crate::engine::op::{spawn_op, Op, OpBypass, OpCategory, OpOwner, OpRef, OpTypeName};
+use crate::engine::param::{
+    ParamDefault, ParamExpression, ParamLayer, ParamLink, ParamMeta, ParamName, ParamOrder,
+    ParamOverride, ParamPage, ParamProvenance, ParamValue, ScriptedParamError,
+};
+use crate::engine::preset::{recall_preset, save_preset, Presets};
+use crate::engine::project::{OpData, ParamDataValue};
+use crate::engine::script::asset::{Script, ASSETS_DIR};
+use crate::engine::script::errors::ScriptErrors;
+use crate::index::{CompositeIndex2, Index, IndexPlugin, UniqueIndex};
+use crate::ui::clipboard::ClipboardPlugin;
+use crate::ui::console::ConsolePlugin;
+use crate::ui::graph::{CurrentContainer, GraphPlugin, NodePosition, SelectedNode};
+use crate::ui::grid::{InfiniteGrid, InfiniteGridPlugin};
+use crate::ui::timeline::TimelinePlugin;
 use crate::Sets::Ui;
 
 mod camera;
+pub mod clipboard;
+pub mod console;
 pub mod graph;
 pub mod grid;
+pub mod timeline;
 
 pub struct SepiascrapedUiPlugin;
 
@@ -37,6 +57,9 @@ impl Plugin for SepiascrapedUiPlugin {
             EguiPlugin,
             ShapePlugin,
             GraphPlugin,
+            ClipboardPlugin,
+            TimelinePlugin,
+            ConsolePlugin,
             CameraControllerPlugin,
             InfiniteGridPlugin,
             DefaultPickingPlugins,
@@ -48,7 +71,10 @@ impl Plugin for SepiascrapedUiPlugin {
         ))
         .add_event::<ClickNode>()
         .add_systems(Startup, ui_setup)
-        .add_systems(Update, (init_params, ui, selected_node_ui).in_set(Ui))
+        .add_systems(
+            Update,
+            (init_params, ui, selected_node_ui, node_menu).in_set(Ui),
+        )
         .init_resource::<UiState>()
         .insert_resource(AmbientLight {
             color: Color::WHITE,
@@ -76,10 +102,17 @@ pub struct UiState {
     pub top_panel: Option<egui::Response>,
     pub node_info: Option<egui::Response>,
     pub node_menu: Option<NodeMenuState>,
+    pub timeline: Option<egui::Response>,
+    pub console: Option<egui::Response>,
 }
 
+/// The node creation menu, opened on the grid with tab or right-click.
 pub struct NodeMenuState {
+    /// Where the menu was opened, in window coordinates.
     pub pos: (f32, f32),
+    /// Where new nodes are placed, relative to the grid.
+    pub grid_pos: Vec2,
+    pub search: String,
 }
 
 // ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
@@ -105,6 +138,11 @@ pub fn ui(
     keys: Res<ButtonInput<KeyCode>>,
     mut egui_contexts: EguiContexts,
     diagnostics_store: Res<DiagnosticsStore>,
+    graph_state: Res<GraphState>,
+    mut current_container: ResMut<CurrentContainer>,
+    history: Res<History>,
+    mut ev_undo: EventWriter<Undo>,
+    mut ev_redo: EventWriter<Redo>,
 ) {
     let ctx = egui_contexts.ctx_mut();
 
@@ -127,12 +165,192 @@ pub fn ui(
                     ui.label(format!("Time: {:.2}", time.elapsed_seconds()));
                     ui.label(format!("Frames: {:.2}", frame_count.0));
                     ui.label(format!("FPS: {:.2}", fps.unwrap_or(0.0)));
+                    let path = current_container
+                        .0
+                        .as_ref()
+                        .map_or("", |container| container.0.as_str());
+                    ui.label(format!("Network: /{}", path));
+                    if current_container.0.is_some() && ui.button("Up").clicked() {
+                        current_container.up();
+                    }
+                    if ui
+                        .add_enabled(history.can_undo(), egui::Button::new("Undo"))
+                        .clicked()
+                    {
+                        ev_undo.send(Undo);
+                    }
+                    if ui
+                        .add_enabled(history.can_redo(), egui::Button::new("Redo"))
+                        .clicked()
+                    {
+                        ev_redo.send(Redo);
+                    }
+                    if let Some(err) = &graph_state.error {
+                        ui.colored_label(egui::Color32::RED, err.to_string());
+                    }
                 });
             })
             .response,
     );
 }
 
+/// A slider over a param's range. A soft range can be exceeded by typing in a value, which is
+/// then clamped to the param's hard range by [crate::engine::param::validate].
+fn param_slider<'a, N: egui::emath::Numeric>(
+    value: &'a mut N,
+    meta: &ParamMeta,
+) -> egui::Slider<'a> {
+    let min = meta.soft_min.or(meta.min).unwrap_or(0.0) as f64;
+    let max = meta.soft_max.or(meta.max).unwrap_or(100.0) as f64;
+    let mut slider = egui::Slider::new(value, N::from_f64(min)..=N::from_f64(max))
+        .clamp_to_range(meta.soft_min.is_none() && meta.soft_max.is_none());
+    if let Some(step) = meta.step {
+        slider = slider.step_by(step);
+    }
+    if let Some(unit) = &meta.unit {
+        slider = slider.suffix(format!(" {}", unit));
+    }
+    slider
+}
+
+/// The tab label of a param page.
+fn page_label(page: &str) -> &str {
+    if page.is_empty() {
+        "Params"
+    } else {
+        page
+    }
+}
+
+/// A text field for a path, with a menu to pick one of the files in the assets directory.
+fn path_picker(ui: &mut egui::Ui, path: &mut PathBuf) {
+    let mut text = path.to_string_lossy().to_string();
+    if ui.text_edit_singleline(&mut text).changed() {
+        *path = PathBuf::from(text);
+    }
+    ui.menu_button("...", |ui| {
+        let mut files = vec![];
+        asset_files(Path::new(ASSETS_DIR), &mut files);
+        files.sort();
+        egui::ScrollArea::vertical()
+            .max_height(300.0)
+            .show(ui, |ui| {
+                for file in files {
+                    let selected = *path == file;
+                    if ui
+                        .selectable_label(selected, file.to_string_lossy())
+                        .clicked()
+                    {
+                        *path = file;
+                        ui.close_menu();
+                    }
+                }
+            });
+    });
+}
+
+/// Collect the files in a directory of the assets, relative to the assets directory.
+fn asset_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            asset_files(&path, files);
+        } else if let Ok(file) = path.strip_prefix(ASSETS_DIR) {
+            files.push(file.to_path_buf());
+        }
+    }
+}
+
+/// Save the op as a preset, and recall or delete the presets captured from it.
+fn presets_ui(ui: &mut egui::Ui, commands: &mut Commands, presets: &Presets, op: &OpName) {
+    // The name of a new preset and how long recalling takes
+    let id = ui.make_persistent_id(("preset_ui", &op.0));
+    let (mut name, mut seconds) = ui
+        .data_mut(|data| data.get_temp::<(String, f32)>(id))
+        .unwrap_or_default();
+
+    egui::Grid::new(("op_presets", &op.0))
+        .min_col_width(100.0)
+        .show(ui, |ui| {
+            let captured = presets.iter().filter(|(_, preset)| preset.op == op.0);
+            for (preset, _) in captured {
+                ui.label(preset);
+                ui.horizontal(|ui| {
+                    if ui.button("Recall").clicked() {
+                        let preset = preset.clone();
+                        commands.add(move |world: &mut World| {
+                            if let Err(err) = recall_preset(world, &preset, seconds) {
+                                warn!("Failed to recall preset: {}", err);
+                            }
+                        });
+                    }
+                    if ui.button("Delete").clicked() {
+                        let preset = preset.clone();
+                        commands.add(move |world: &mut World| {
+                            world.resource_mut::<Presets>().remove(&preset);
+                        });
+                    }
+                });
+                ui.end_row();
+            }
+
+            ui.label("Morph");
+            ui.add(
+                egui::DragValue::new(&mut seconds)
+                    .clamp_range(0.0..=60.0)
+                    .speed(0.05)
+                    .suffix(" s"),
+            );
+            ui.end_row();
+            ui.add(egui::TextEdit::singleline(&mut name).hint_text("Preset name"));
+            if ui
+                .add_enabled(!name.is_empty(), egui::Button::new("Save"))
+                .clicked()
+            {
+                let (name, op) = (std::mem::take(&mut name), op.0.clone());
+                commands.add(move |world: &mut World| {
+                    if let Err(err) = save_preset(world, name, &op) {
+                        warn!("Failed to save preset: {}", err);
+                    }
+                });
+            }
+            ui.end_row();
+        });
+
+    ui.data_mut(|data| data.insert_temp(id, (name, seconds)));
+}
+
+/// A grid of params, one per row.
+fn param_grid(
+    ui: &mut egui::Ui,
+    id: impl std::hash::Hash,
+    params: &[Entity],
+    mut param_row: impl FnMut(&mut egui::Ui, Entity),
+) {
+    egui::Grid::new(id).min_col_width(100.0).show(ui, |ui| {
+        for param in params {
+            param_row(ui, *param);
+        }
+    });
+}
+
+/// A dropdown of a param's labeled choices.
+fn param_choices(ui: &mut egui::Ui, param: Entity, value: &mut u32, meta: &ParamMeta) {
+    let selected = meta
+        .choice_label(*value)
+        .map_or(value.to_string(), str::to_string);
+    egui::ComboBox::from_id_source(param)
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            for (label, choice) in meta.choices.iter() {
+                ui.selectable_value(value, *choice, label);
+            }
+        });
+}
+
 pub fn init_params(
     mut commands: Commands,
     params_q: Query<(Entity, &ParamValue), Added<ParamValue>>,
@@ -152,310 +370,858 @@ pub fn init_params(
 }
 
 pub fn selected_node_ui(
+    mut commands: Commands,
     mut ui_state: ResMut<UiState>,
     mut egui_contexts: EguiContexts,
-    selected_q: Query<(&Children, &OpTypeName), With<SelectedNode>>,
+    selected_q: Query<
+        (
+            Entity,
+            &Children,
+            &OpTypeName,
+            Has<OpBypass>,
+            Option<&OpStats>,
+            Option<&OpOwner>,
+            Option<&Handle<Script>>,
+        ),
+        With<SelectedNode>,
+    >,
     mut params_q: Query<(
         Entity,
         &ParamName,
         &mut ParamValue,
-        Has<ScriptedParam>,
+        &ParamOrder,
+        &ParamPage,
+        &ParamMeta,
+        Option<&ParamDefault>,
+        Option<&ParamExpression>,
+        Option<&ParamLink>,
+        Option<&Keyframes>,
+        Option<&mut ParamProvenance>,
         Option<&ScriptedParamError>,
         Option<&mut UiText>,
     )>,
     mut op_name_q: Query<&OpName>,
     op_name_idx: Res<UniqueIndex<OpName>>,
+    param_idx: Res<CompositeIndex2<OpRef, ParamName>>,
     category_idx: Res<Index<OpCategory>>,
     op_type_idx: Res<Index<OpTypeName>>,
+    registry: Res<OpRegistry>,
+    mut history: ResMut<History>,
+    mut copied_param: Local<Option<ParamLink>>,
+    timeline: Res<Timeline>,
+    mut selected_pages: Local<HashMap<Entity, String>>,
+    (presets, script_errors): (Res<Presets>, Res<ScriptErrors>),
 ) {
-    if let Ok((children, op_type_name)) = selected_q.get_single() {
+    if let Ok((op, children, op_type_name, bypass, stats, owner, script)) = selected_q.get_single()
+    {
+        let title = registry
+            .get(op_type_name.0)
+            .map_or(op_type_name.0, |op| op.display_name);
+
+        // Sorting is stable, so params with the same order keep the order they were spawned in
+        let mut params = children
+            .iter()
+            .filter_map(|entity| params_q.get(*entity).ok())
+            .map(|(entity, _, _, order, page, meta, ..)| {
+                (order.0, page.0.clone(), meta.section.clone(), entity)
+            })
+            .collect::<Vec<_>>();
+        params.sort_by_key(|(order, ..)| *order);
+
+        let mut pages = Vec::<String>::new();
+        for (_, page, ..) in &params {
+            if !pages.contains(page) {
+                pages.push(page.clone());
+            }
+        }
+        pages.sort_by_key(|page| !page.is_empty());
+        let selected_page = selected_pages.entry(op).or_default();
+        if !pages.contains(selected_page) {
+            *selected_page = pages.first().cloned().unwrap_or_default();
+        }
+
+        // Params outside of a section come first, then each section in the order of its params
+        let mut sections = Vec::<(Option<String>, Vec<Entity>)>::new();
+        for (_, page, section, entity) in &params {
+            if page != &*selected_page {
+                continue;
+            }
+            match sections.iter_mut().find(|(other, _)| other == section) {
+                Some((_, entities)) => entities.push(*entity),
+                None => sections.push((section.clone(), vec![*entity])),
+            }
+        }
+        sections.sort_by_key(|(section, _)| section.is_some());
+
+        // Errors from the script that created the op or the script attached to it
+        let mut errors = vec![];
+        if let Some(OpOwner::Script(id)) = owner {
+            errors.extend(script_errors.for_script(*id));
+        }
+        if let Some(script) = script.filter(|script| owner != Some(&OpOwner::Script(script.id()))) {
+            errors.extend(script_errors.for_script(script.id()));
+        }
+
+        let op_name = op_name_q.get(op).ok().cloned();
         ui_state.node_info = Some(
-            egui::Window::new(op_type_name.0)
+            egui::Window::new(title)
                 .anchor(egui::Align2::LEFT_TOP, egui::Vec2::new(10.0, 30.0))
                 .resizable(false)
                 .collapsible(false)
                 .movable(false)
                 .show(egui_contexts.ctx_mut(), |ui| {
-                    egui::Grid::new("op_params")
+                    for report in &errors {
+                        ui.colored_label(egui::Color32::RED, report.to_string());
+                    }
+                    if !errors.is_empty() {
+                        ui.separator();
+                    }
+                    egui::Grid::new("op_bypass")
                         .min_col_width(100.0)
                         .show(ui, |ui| {
-                            ui.heading("Params");
-                            ui.end_row();
-                            ui.separator();
+                            ui.label("Bypass");
+                            let mut is_bypassed = bypass;
+                            if ui.checkbox(&mut is_bypassed, "").changed() {
+                                if is_bypassed {
+                                    commands.entity(op).insert(OpBypass);
+                                } else {
+                                    commands.entity(op).remove::<OpBypass>();
+                                }
+                            }
                             ui.end_row();
-                            for entity in children {
-                                let (param, name, mut value, is_scripted, script_error, ui_text) =
-                                    params_q.get_mut(*entity).expect("Failed to get param");
-                                ui.label(name.0.to_string() + if is_scripted { " *" } else { "" });
-
-                                match value.as_mut() {
-                                    ParamValue::Color(color) => {
-                                        ui.add_enabled_ui(!is_scripted, |ui| {
-                                            ui.color_edit_button_rgba_premultiplied(color.as_mut())
-                                        });
+                            if let Some(owner) = owner {
+                                ui.label("Owner");
+                                ui.horizontal(|ui| {
+                                    ui.label(owner.as_str());
+                                    // Hand the op over to the project, like persist!
+                                    if !owner.is_saved() && ui.button("Persist").clicked() {
+                                        commands.entity(op).insert(OpOwner::User);
                                     }
-                                    ParamValue::F32(f) => {
-                                        ui.add_enabled_ui(!is_scripted, |ui| {
-                                            ui.add(egui::Slider::new(f, 0.0..=100.0))
-                                        });
-                                    }
-                                    ParamValue::Vec2(v) => {
-                                        ui.add_enabled_ui(!is_scripted, |ui| {
-                                            ui.add(
-                                                egui::DragValue::new(&mut v.x)
-                                                    .clamp_range(0.0..=1.0)
-                                                    .speed(0.05),
-                                            );
-                                            ui.add(
-                                                egui::DragValue::new(&mut v.y)
-                                                    .clamp_range(0.0..=1.0)
-                                                    .speed(0.05),
-                                            );
-                                        });
-                                    }
-                                    ParamValue::None => {}
-                                    ParamValue::U32(x) => {
-                                        ui.add_enabled_ui(!is_scripted, |ui| {
-                                            ui.add(egui::Slider::new(x, 0..=100))
-                                        });
-                                    }
-                                    ParamValue::Bool(x) => {
-                                        ui.add_enabled_ui(!is_scripted, |ui| ui.checkbox(x, ""));
+                                });
+                                ui.end_row();
+                            }
+                        });
+
+                    if pages.len() > 1 {
+                        ui.horizontal(|ui| {
+                            for page in &pages {
+                                ui.selectable_value(selected_page, page.clone(), page_label(page));
+                            }
+                        });
+                    } else {
+                        ui.heading("Params");
+                    }
+                    ui.separator();
+
+                    let mut param_row = |ui: &mut egui::Ui, entity: Entity| {
+                        let (
+                            param,
+                            name,
+                            mut value,
+                            _,
+                            _,
+                            meta,
+                            default,
+                            expression,
+                            link,
+                            keyframes,
+                            provenance,
+                            script_error,
+                            ui_text,
+                        ) = params_q.get_mut(entity).expect("Failed to get param");
+                        let before = value.clone();
+                        let expression_before = expression.map(|expression| expression.0.clone());
+                        let mut expression = expression_before.clone();
+                        let link_before = link.cloned();
+                        let mut link = link_before.clone();
+                        let keyframes_before = keyframes.cloned();
+                        let mut keyframes = keyframes_before.clone();
+                        let key = |value: &ParamValue| Keyframe {
+                            time: timeline.time,
+                            value: value.clone(),
+                            interpolation: Interpolation::default(),
+                        };
+                        // Params set every frame can only be edited by overriding them, except
+                        // animated ones, which are keyed instead
+                        let layer = provenance
+                            .as_ref()
+                            .map_or(ParamLayer::Default, |p| p.active);
+                        let is_driven = layer.is_driven()
+                            && !matches!(layer, ParamLayer::Animation | ParamLayer::Override);
+                        let this_param = op_name_q.get(op).ok().map(|op| ParamLink {
+                            op: op.0.clone(),
+                            param: name.0.clone(),
+                        });
+
+                        let label = ui.add(
+                            egui::Label::new(if layer.is_driven() {
+                                format!("{} ({})", name.0, layer.as_str())
+                            } else {
+                                name.0.clone()
+                            })
+                            .sense(egui::Sense::click_and_drag()),
+                        );
+                        let layer_hint = format!("Value from the {} layer", layer.as_str());
+                        let label = match &meta.tooltip {
+                            Some(tooltip) => {
+                                label.on_hover_text(format!("{}\n\n{}", tooltip, layer_hint))
+                            }
+                            None => label.on_hover_text(layer_hint),
+                        };
+                        // Drag a param onto another to link it
+                        if let Some(this_param) = &this_param {
+                            label.dnd_set_drag_payload(this_param.clone());
+                        }
+                        if let Some(source) = label.dnd_release_payload::<ParamLink>() {
+                            if Some(source.as_ref()) != this_param.as_ref() {
+                                link = Some(source.as_ref().clone());
+                            }
+                        }
+                        label.context_menu(|ui| {
+                            let reset = ui.add_enabled(
+                                !is_driven && default.is_some(),
+                                egui::Button::new("Reset to default"),
+                            );
+                            if reset.clicked() {
+                                if let Some(default) = default {
+                                    *value = default.0.clone();
+                                }
+                                ui.close_menu();
+                            }
+                            if is_driven && ui.button("Override").clicked() {
+                                commands.entity(param).insert(ParamOverride(value.clone()));
+                                ui.close_menu();
+                            }
+                            if layer == ParamLayer::Override && ui.button("Release").clicked() {
+                                commands.entity(param).remove::<ParamOverride>();
+                                ui.close_menu();
+                            }
+                            ui.separator();
+                            if expression.is_some() {
+                                if ui.button("Remove expression").clicked() {
+                                    expression = None;
+                                    ui.close_menu();
+                                }
+                            } else if ui.button("Add expression").clicked() {
+                                expression = Some(String::new());
+                                ui.close_menu();
+                            }
+                            ui.separator();
+                            if ui.button("Copy reference").clicked() {
+                                *copied_param = this_param.clone();
+                                ui.close_menu();
+                            }
+                            let paste = ui.add_enabled(
+                                copied_param.is_some() && *copied_param != this_param,
+                                egui::Button::new("Paste link"),
+                            );
+                            if paste.clicked() {
+                                link = copied_param.clone();
+                                ui.close_menu();
+                            }
+                            if link.is_some() && ui.button("Unlink").clicked() {
+                                link = None;
+                                ui.close_menu();
+                            }
+                            if Keyframes::can_animate(&value) {
+                                ui.separator();
+                                let set_key =
+                                    ui.add_enabled(!is_driven, egui::Button::new("Set key"));
+                                if set_key.clicked() {
+                                    keyframes
+                                        .get_or_insert_with(Keyframes::default)
+                                        .insert(key(&value));
+                                    ui.close_menu();
+                                }
+                                if keyframes.is_some() && ui.button("Clear keys").clicked() {
+                                    keyframes = None;
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+
+                        match value.as_mut() {
+                            ParamValue::Color(color) => {
+                                ui.add_enabled_ui(!is_driven, |ui| {
+                                    ui.color_edit_button_rgba_premultiplied(color.as_mut())
+                                });
+                            }
+                            ParamValue::F32(f) => {
+                                ui.add_enabled_ui(!is_driven, |ui| ui.add(param_slider(f, meta)));
+                            }
+                            ParamValue::Vec2(v) => {
+                                ui.add_enabled_ui(!is_driven, |ui| {
+                                    ui.add(
+                                        egui::DragValue::new(&mut v.x)
+                                            .clamp_range(0.0..=1.0)
+                                            .speed(0.05),
+                                    );
+                                    ui.add(
+                                        egui::DragValue::new(&mut v.y)
+                                            .clamp_range(0.0..=1.0)
+                                            .speed(0.05),
+                                    );
+                                });
+                            }
+                            ParamValue::None => {}
+                            ParamValue::U32(x) => {
+                                ui.add_enabled_ui(!is_driven, |ui| {
+                                    if meta.choices.is_empty() {
+                                        ui.add(param_slider(x, meta));
+                                    } else {
+                                        param_choices(ui, param, x, meta);
                                     }
-                                    ParamValue::TextureOp(x) => {
-                                        let mut ui_text = ui_text.expect("Failed to get ui_text");
-
-                                        if let Some(entity) = x {
-                                            let name = op_name_q.get(*entity).unwrap();
-                                            *ui_text = UiText(name.0.clone());
-                                        };
-                                        ui.add_enabled_ui(!is_scripted, |ui| {
-                                            // TODO: compute this in resource
-                                            let inputs = category_idx
-                                                .get(&OpCategory(
-                                                    crate::engine::op::texture::CATEGORY,
-                                                ))
-                                                .unwrap_or(&vec![])
-                                                .iter()
-                                                .map(|e| op_name_q.get(*e).unwrap().0.clone())
-                                                .collect::<BTreeSet<_>>();
-
-                                        });
-                                        if !ui_text.0.is_empty() {
-                                            if let Some(entity) =
-                                                op_name_idx.get(&OpName(ui_text.0.clone()))
-                                            {
-                                                *x = Some(entity.clone());
-                                            }
-                                        }
+                                });
+                            }
+                            ParamValue::Bool(x) => {
+                                ui.add_enabled_ui(!is_driven, |ui| ui.checkbox(x, ""));
+                            }
+                            ParamValue::I32(x) => {
+                                ui.add_enabled_ui(!is_driven, |ui| {
+                                    ui.add(param_slider(x, meta));
+                                });
+                            }
+                            ParamValue::Vec4(v) => {
+                                ui.add_enabled_ui(!is_driven, |ui| {
+                                    for x in v.as_mut() {
+                                        ui.add(egui::DragValue::new(x).speed(0.05));
                                     }
-                                    ParamValue::MeshOp(x) => {
-                                        let mut ui_text = ui_text.expect("Failed to get ui_text");
-
-                                        if let Some(entity) = x {
-                                            let name = op_name_q.get(*entity).unwrap();
-                                            *ui_text = UiText(name.0.clone());
-                                        };
-                                        ui.add_enabled_ui(!is_scripted, |ui| {
-                                            // TODO: compute this in resource
-                                            let inputs = category_idx
-                                                .get(&OpCategory(crate::engine::op::mesh::CATEGORY))
-                                                .unwrap_or(&vec![])
-                                                .iter()
-                                                .map(|e| op_name_q.get(*e).unwrap().0.clone())
-                                                .collect::<BTreeSet<_>>();
-                                        });
-                                        if !ui_text.0.is_empty() {
-                                            if let Some(entity) =
-                                                op_name_idx.get(&OpName(ui_text.0.clone()))
-                                            {
-                                                *x = Some(entity.clone());
+                                });
+                            }
+                            ParamValue::Mat4(m) => {
+                                ui.add_enabled_ui(!is_driven, |ui| {
+                                    egui::Grid::new(("mat4", param)).show(ui, |ui| {
+                                        for row in 0..4 {
+                                            for col in 0..4 {
+                                                ui.add(
+                                                    egui::DragValue::new(&mut m.col_mut(col)[row])
+                                                        .speed(0.05),
+                                                );
                                             }
+                                            ui.end_row();
                                         }
+                                    });
+                                });
+                            }
+                            ParamValue::String(x) => {
+                                ui.add_enabled_ui(!is_driven, |ui| ui.text_edit_singleline(x));
+                            }
+                            ParamValue::Path(x) => {
+                                ui.add_enabled_ui(!is_driven, |ui| path_picker(ui, x));
+                            }
+                            ParamValue::Pulse(x) => {
+                                ui.add_enabled_ui(!is_driven, |ui| {
+                                    if ui.button("Pulse").clicked() {
+                                        *x = true;
                                     }
-                                    ParamValue::MaterialOp(x) => {
-                                        let mut ui_text = ui_text.expect("Failed to get ui_text");
-
-                                        if let Some(entity) = x {
-                                            let name = op_name_q.get(*entity).unwrap();
-                                            *ui_text = UiText(name.0.clone());
-                                        };
-                                        ui.add_enabled_ui(!is_scripted, |ui| {
-                                            // TODO: compute this in resource
-                                            let inputs = category_idx
-                                                .get(&OpCategory(
-                                                    crate::engine::op::material::CATEGORY,
-                                                ))
-                                                .unwrap_or(&vec![])
-                                                .iter()
-                                                .map(|e| op_name_q.get(*e).unwrap().0.clone())
-                                                .collect::<BTreeSet<_>>();
-                                        });
-                                        if !ui_text.0.is_empty() {
-                                            if let Some(entity) =
-                                                op_name_idx.get(&OpName(ui_text.0.clone()))
-                                            {
-                                                *x = Some(entity.clone());
-                                            }
-                                        }
+                                });
+                            }
+                            ParamValue::TextureOp(x) => {
+                                let mut ui_text = ui_text.expect("Failed to get ui_text");
+
+                                if let Some(name) = x.and_then(|e| op_name_q.get(e).ok()) {
+                                    *ui_text = UiText(name.0.clone());
+                                };
+                                ui.add_enabled_ui(!is_driven, |ui| {
+                                    // TODO: compute this in resource
+                                    let inputs = category_idx
+                                        .get(&OpCategory(crate::engine::op::texture::CATEGORY))
+                                        .unwrap_or(&vec![])
+                                        .iter()
+                                        .map(|e| op_name_q.get(*e).unwrap().0.clone())
+                                        .collect::<BTreeSet<_>>();
+                                });
+                                if !ui_text.0.is_empty() {
+                                    if let Some(entity) =
+                                        op_name_idx.get(&OpName(ui_text.0.clone()))
+                                    {
+                                        *x = Some(entity.clone());
                                     }
-                                    ParamValue::Vec3(v) => {
-                                        ui.add_enabled_ui(!is_scripted, |ui| {
-                                            ui.add(
-                                                egui::DragValue::new(&mut v.x)
-                                                    .clamp_range(-10.0..=10.0)
-                                                    .speed(0.05),
-                                            );
-                                            ui.add(
-                                                egui::DragValue::new(&mut v.y)
-                                                    .clamp_range(-10.0..=10.0)
-                                                    .speed(0.05),
-                                            );
-                                            ui.add(
-                                                egui::DragValue::new(&mut v.z)
-                                                    .clamp_range(-10.0..=10.0)
-                                                    .speed(0.05),
-                                            );
-                                        });
+                                }
+                            }
+                            ParamValue::MeshOp(x) => {
+                                let mut ui_text = ui_text.expect("Failed to get ui_text");
+
+                                if let Some(name) = x.and_then(|e| op_name_q.get(e).ok()) {
+                                    *ui_text = UiText(name.0.clone());
+                                };
+                                ui.add_enabled_ui(!is_driven, |ui| {
+                                    // TODO: compute this in resource
+                                    let inputs = category_idx
+                                        .get(&OpCategory(crate::engine::op::mesh::CATEGORY))
+                                        .unwrap_or(&vec![])
+                                        .iter()
+                                        .map(|e| op_name_q.get(*e).unwrap().0.clone())
+                                        .collect::<BTreeSet<_>>();
+                                });
+                                if !ui_text.0.is_empty() {
+                                    if let Some(entity) =
+                                        op_name_idx.get(&OpName(ui_text.0.clone()))
+                                    {
+                                        *x = Some(entity.clone());
                                     }
-                                    ParamValue::Quat(v) => {
-                                        ui.add_enabled_ui(!is_scripted, |ui| {
-                                            ui.add(
-                                                egui::DragValue::new(&mut v.x)
-                                                    .clamp_range(-10.0..=10.0)
-                                                    .speed(0.05),
-                                            );
-                                            ui.add(
-                                                egui::DragValue::new(&mut v.y)
-                                                    .clamp_range(-10.0..=10.0)
-                                                    .speed(0.05),
-                                            );
-                                            ui.add(
-                                                egui::DragValue::new(&mut v.z)
-                                                    .clamp_range(-10.0..=10.0)
-                                                    .speed(0.05),
-                                            );
-                                            ui.add(
-                                                egui::DragValue::new(&mut v.w)
-                                                    .clamp_range(0.0..=1.0)
-                                                    .speed(0.05),
-                                            );
-                                        });
+                                }
+                            }
+                            ParamValue::MaterialOp(x) => {
+                                let mut ui_text = ui_text.expect("Failed to get ui_text");
+
+                                if let Some(name) = x.and_then(|e| op_name_q.get(e).ok()) {
+                                    *ui_text = UiText(name.0.clone());
+                                };
+                                ui.add_enabled_ui(!is_driven, |ui| {
+                                    // TODO: compute this in resource
+                                    let inputs = category_idx
+                                        .get(&OpCategory(crate::engine::op::material::CATEGORY))
+                                        .unwrap_or(&vec![])
+                                        .iter()
+                                        .map(|e| op_name_q.get(*e).unwrap().0.clone())
+                                        .collect::<BTreeSet<_>>();
+                                });
+                                if !ui_text.0.is_empty() {
+                                    if let Some(entity) =
+                                        op_name_idx.get(&OpName(ui_text.0.clone()))
+                                    {
+                                        *x = Some(entity.clone());
                                     }
-                                    ParamValue::UVec2(x) => {
-                                        ui.add_enabled_ui(!is_scripted, |ui| {
-                                            ui.add(
-                                                egui::DragValue::new(&mut x.x)
-                                                    .clamp_range(0..=10000)
-                                                    .speed(10.0),
-                                            );
-                                            ui.add(
-                                                egui::DragValue::new(&mut x.y)
-                                                    .clamp_range(0..=10000)
-                                                    .speed(10.0),
+                                }
+                            }
+                            ParamValue::Vec3(v) => {
+                                ui.add_enabled_ui(!is_driven, |ui| {
+                                    ui.add(
+                                        egui::DragValue::new(&mut v.x)
+                                            .clamp_range(-10.0..=10.0)
+                                            .speed(0.05),
+                                    );
+                                    ui.add(
+                                        egui::DragValue::new(&mut v.y)
+                                            .clamp_range(-10.0..=10.0)
+                                            .speed(0.05),
+                                    );
+                                    ui.add(
+                                        egui::DragValue::new(&mut v.z)
+                                            .clamp_range(-10.0..=10.0)
+                                            .speed(0.05),
+                                    );
+                                });
+                            }
+                            ParamValue::Quat(v) => {
+                                ui.add_enabled_ui(!is_driven, |ui| {
+                                    ui.add(
+                                        egui::DragValue::new(&mut v.x)
+                                            .clamp_range(-10.0..=10.0)
+                                            .speed(0.05),
+                                    );
+                                    ui.add(
+                                        egui::DragValue::new(&mut v.y)
+                                            .clamp_range(-10.0..=10.0)
+                                            .speed(0.05),
+                                    );
+                                    ui.add(
+                                        egui::DragValue::new(&mut v.z)
+                                            .clamp_range(-10.0..=10.0)
+                                            .speed(0.05),
+                                    );
+                                    ui.add(
+                                        egui::DragValue::new(&mut v.w)
+                                            .clamp_range(0.0..=1.0)
+                                            .speed(0.05),
+                                    );
+                                });
+                            }
+                            ParamValue::UVec2(x) => {
+                                let min = meta.min.unwrap_or(0.0) as u32;
+                                let max = meta.max.unwrap_or(10000.0) as u32;
+                                let suffix = meta
+                                    .unit
+                                    .as_ref()
+                                    .map_or(String::new(), |unit| format!(" {}", unit));
+                                ui.add_enabled_ui(!is_driven, |ui| {
+                                    ui.add(
+                                        egui::DragValue::new(&mut x.x)
+                                            .clamp_range(min..=max)
+                                            .speed(10.0)
+                                            .suffix(&suffix),
+                                    );
+                                    ui.add(
+                                        egui::DragValue::new(&mut x.y)
+                                            .clamp_range(min..=max)
+                                            .speed(10.0)
+                                            .suffix(&suffix),
+                                    );
+                                });
+                            }
+                            ParamValue::CameraOps(x) => {
+                                let mut ui_text = ui_text.expect("Failed to get ui_text");
+                                ui.add_enabled_ui(!is_driven, |ui| {
+                                    ui.text_edit_singleline(&mut ui_text.0);
+                                });
+
+                                if !ui_text.0.is_empty() {
+                                    let names = ui_text.split(',').collect::<Vec<_>>();
+                                    let mut entities = vec![];
+                                    for name in names {
+                                        if name == "*" {
+                                            entities.extend(
+                                                op_type_idx
+                                                    .get(&OpTypeName(ComponentOpCamera::NAME))
+                                                    .unwrap_or(&vec![]),
                                             );
-                                        });
-                                    }
-                                    ParamValue::CameraOps(x) => {
-                                        let mut ui_text = ui_text.expect("Failed to get ui_text");
-                                        ui.add_enabled_ui(!is_scripted, |ui| {
-                                            ui.text_edit_singleline(&mut ui_text.0);
-                                        });
-
-                                        if !ui_text.0.is_empty() {
-                                            let names = ui_text.split(',').collect::<Vec<_>>();
-                                            let mut entities = vec![];
-                                            for name in names {
-                                                if name == "*" {
-                                                    entities.extend(
-                                                        op_type_idx
-                                                            .get(&OpTypeName(OpType::<
-                                                                ComponentOpCamera,
-                                                            >::name(
-                                                            )))
-                                                            .unwrap_or(&vec![]),
-                                                    );
-                                                    continue;
-                                                }
-
-                                                if let Some(entity) =
-                                                    op_name_idx.get(&OpName(name.to_string()))
-                                                {
-                                                    entities.push(entity.clone());
-                                                } else {
-                                                    // We didn't find this one, that's probably an error
-                                                    let prev_color =
-                                                        ui.visuals_mut().override_text_color;
-                                                    ui.visuals_mut().override_text_color =
-                                                        Some(egui::Color32::RED);
-                                                    ui.label(format!("Unknown entity: {}", name));
-                                                    ui.visuals_mut().override_text_color =
-                                                        prev_color;
-                                                    ui.end_row();
-                                                }
-                                            }
+                                            continue;
+                                        }
 
-                                            *x = entities;
+                                        if let Some(entity) =
+                                            op_name_idx.get(&OpName(name.to_string()))
+                                        {
+                                            entities.push(entity.clone());
+                                        } else {
+                                            // We didn't find this one, that's probably an error
+                                            let prev_color = ui.visuals_mut().override_text_color;
+                                            ui.visuals_mut().override_text_color =
+                                                Some(egui::Color32::RED);
+                                            ui.label(format!("Unknown entity: {}", name));
+                                            ui.visuals_mut().override_text_color = prev_color;
+                                            ui.end_row();
                                         }
                                     }
-                                    ParamValue::LightOps(x) => {
-                                        let mut ui_text = ui_text.expect("Failed to get ui_text");
-                                        ui.add_enabled_ui(!is_scripted, |ui| {
-                                            ui.text_edit_singleline(&mut ui_text.0);
-                                        });
-
-                                        if !ui_text.0.is_empty() {
-                                            let names = ui_text.split(',').collect::<Vec<_>>();
-                                            let mut entities = vec![];
-                                            for name in names {
-                                                if name == "*" {
-                                                    entities.extend(
-                                                        op_type_idx
-                                                            .get(&OpTypeName(OpType::<
-                                                                ComponentOpLight,
-                                                            >::name(
-                                                            )))
-                                                            .unwrap_or(&vec![]),
-                                                    );
-                                                    continue;
-                                                }
-
-                                                if let Some(entity) =
-                                                    op_name_idx.get(&OpName(name.to_string()))
-                                                {
-                                                    entities.push(entity.clone());
-                                                } else {
-                                                    // We didn't find this one, that's probably an error
-                                                    let prev_color =
-                                                        ui.visuals_mut().override_text_color;
-                                                    ui.visuals_mut().override_text_color =
-                                                        Some(egui::Color32::RED);
-                                                    ui.label(format!("Unknown entity: {}", name));
-                                                    ui.visuals_mut().override_text_color =
-                                                        prev_color;
-                                                    ui.end_row();
-                                                }
-                                            }
 
-                                            *x = entities;
+                                    *x = entities;
+                                }
+                            }
+                            ParamValue::LightOps(x) => {
+                                let mut ui_text = ui_text.expect("Failed to get ui_text");
+                                ui.add_enabled_ui(!is_driven, |ui| {
+                                    ui.text_edit_singleline(&mut ui_text.0);
+                                });
+
+                                if !ui_text.0.is_empty() {
+                                    let names = ui_text.split(',').collect::<Vec<_>>();
+                                    let mut entities = vec![];
+                                    for name in names {
+                                        if name == "*" {
+                                            entities.extend(
+                                                op_type_idx
+                                                    .get(&OpTypeName(ComponentOpLight::NAME))
+                                                    .unwrap_or(&vec![]),
+                                            );
+                                            continue;
+                                        }
+
+                                        if let Some(entity) =
+                                            op_name_idx.get(&OpName(name.to_string()))
+                                        {
+                                            entities.push(entity.clone());
+                                        } else {
+                                            // We didn't find this one, that's probably an error
+                                            let prev_color = ui.visuals_mut().override_text_color;
+                                            ui.visuals_mut().override_text_color =
+                                                Some(egui::Color32::RED);
+                                            ui.label(format!("Unknown entity: {}", name));
+                                            ui.visuals_mut().override_text_color = prev_color;
+                                            ui.end_row();
                                         }
                                     }
+
+                                    *x = entities;
                                 }
+                            }
+                        }
+                        // Editing an overridden param moves the value it's held at, which is
+                        // temporary so isn't recorded. Editing an animated param keys it at the
+                        // playhead.
+                        if *value != before && layer == ParamLayer::Override {
+                            commands.entity(param).insert(ParamOverride(value.clone()));
+                        } else if *value != before && keyframes.is_some() {
+                            if let Some(keyframes) = keyframes.as_mut() {
+                                keyframes.insert(key(&value));
+                            }
+                        } else if *value != before && !value.is_triggered() {
+                            if let Some(mut provenance) = provenance {
+                                provenance.base = ParamLayer::Ui;
+                            }
+                            if let Ok(op_name) = op_name_q.get(op) {
+                                history.record(Edit::Param {
+                                    op: op_name.clone(),
+                                    param: name.0.clone(),
+                                    from: ParamDataValue::from_param(&before, &op_name_q),
+                                    to: ParamDataValue::from_param(&value, &op_name_q),
+                                });
+                            }
+                        }
+                        ui.end_row();
+                        if layer == ParamLayer::Override {
+                            ui.label("Override");
+                            if ui.button("Release").clicked() {
+                                commands.entity(param).remove::<ParamOverride>();
+                            }
+                            ui.end_row();
+                        }
+                        if let Some(source) = expression.as_mut() {
+                            ui.label("Expression");
+                            ui.add(
+                                egui::TextEdit::singleline(source)
+                                    .code_editor()
+                                    .hint_text("(* 0.5 (sin *time*))"),
+                            );
+                            ui.end_row();
+                        }
+                        if let Some(link) = &link {
+                            ui.label("Link");
+                            let resolved = link.resolve(&op_name_idx, &param_idx);
+                            if resolved.is_some() {
+                                ui.label(link.to_string());
+                            } else {
+                                ui.colored_label(egui::Color32::RED, format!("{} not found", link));
+                            }
+                            ui.end_row();
+                        }
+                        if keyframes != keyframes_before {
+                            match &keyframes {
+                                Some(keyframes) => {
+                                    commands.entity(param).insert(keyframes.clone());
+                                }
+                                None => {
+                                    commands.entity(param).remove::<Keyframes>();
+                                }
+                            }
+                            if let Ok(op_name) = op_name_q.get(op) {
+                                history.record(Edit::Keyframes {
+                                    op: op_name.clone(),
+                                    param: name.0.clone(),
+                                    from: keyframes_before,
+                                    to: keyframes,
+                                });
+                            }
+                        }
+                        if link != link_before {
+                            match &link {
+                                Some(link) => {
+                                    commands.entity(param).insert(link.clone());
+                                }
+                                None => {
+                                    commands.entity(param).remove::<ParamLink>();
+                                }
+                            }
+                            if let Ok(op_name) = op_name_q.get(op) {
+                                history.record(Edit::Link {
+                                    op: op_name.clone(),
+                                    param: name.0.clone(),
+                                    from: link_before,
+                                    to: link,
+                                });
+                            }
+                        }
+                        if expression != expression_before {
+                            match &expression {
+                                Some(source) => {
+                                    commands
+                                        .entity(param)
+                                        .insert(ParamExpression(source.clone()));
+                                }
+                                None => {
+                                    commands.entity(param).remove::<ParamExpression>();
+                                }
+                            }
+                            if let Ok(op_name) = op_name_q.get(op) {
+                                history.record(Edit::Expression {
+                                    op: op_name.clone(),
+                                    param: name.0.clone(),
+                                    from: expression_before,
+                                    to: expression,
+                                });
+                            }
+                        }
+                        if let Some(error) = script_error {
+                            let prev_color = ui.visuals_mut().override_text_color;
+                            ui.visuals_mut().override_text_color = Some(egui::Color32::RED);
+                            ui.label(error.0.clone());
+                            ui.visuals_mut().override_text_color = prev_color;
+                            ui.end_row();
+                        }
+                    };
+
+                    for (section, entities) in &sections {
+                        match section {
+                            None => param_grid(ui, "op_params", entities, &mut param_row),
+                            Some(section) => {
+                                egui::CollapsingHeader::new(section.as_str())
+                                    .id_source(("op_section", op, section))
+                                    .default_open(true)
+                                    .show(ui, |ui| {
+                                        param_grid(
+                                            ui,
+                                            ("op_params", section),
+                                            entities,
+                                            &mut param_row,
+                                        )
+                                    });
+                            }
+                        }
+                    }
+
+                    if let Some(op_name) = &op_name {
+                        egui::CollapsingHeader::new("Presets")
+                            .id_source(("op_presets", op))
+                            .show(ui, |ui| presets_ui(ui, &mut commands, &presets, op_name));
+                    }
+
+                    egui::Grid::new("op_stats")
+                        .min_col_width(100.0)
+                        .show(ui, |ui| {
+                            if let Some(stats) = stats {
+                                ui.heading("Stats");
+                                ui.end_row();
+                                ui.separator();
+                                ui.end_row();
+                                ui.label("Cooks");
+                                ui.label(stats.cook_count.to_string());
+                                ui.end_row();
+                                ui.label("Cook");
+                                ui.label(format!(
+                                    "{:.3} ms",
+                                    stats.average_cook_time.as_secs_f64() * 1000.0
+                                ));
                                 ui.end_row();
-                                if let Some(error) = script_error {
-                                    let prev_color = ui.visuals_mut().override_text_color;
-                                    ui.visuals_mut().override_text_color = Some(egui::Color32::RED);
-                                    ui.label(error.0.clone());
-                                    ui.visuals_mut().override_text_color = prev_color;
+                                ui.label("Update");
+                                ui.label(format!(
+                                    "{:.3} ms",
+                                    stats.average_update_time.as_secs_f64() * 1000.0
+                                ));
+                                ui.end_row();
+                                if let Some(gpu_time) = stats.gpu_time {
+                                    ui.label("GPU");
+                                    ui.label(format!("{:.3} ms", gpu_time.as_secs_f64() * 1000.0));
                                     ui.end_row();
                                 }
                             }
-                        })
+                        });
                 })
                 .unwrap()
-                .inner
-                .unwrap()
                 .response,
         );
     }
 }
+
+pub fn node_menu(
+    mut commands: Commands,
+    mut ui_state: ResMut<UiState>,
+    mut egui_contexts: EguiContexts,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<UiCamera>>,
+    grid_q: Query<&GlobalTransform, With<InfiniteGrid>>,
+    registry: Res<OpRegistry>,
+    current_container: Res<CurrentContainer>,
+) {
+    let ctx = egui_contexts.ctx_mut();
+
+    let mut just_opened = false;
+    if ui_state.node_menu.is_none() {
+        let open = (keys.just_pressed(KeyCode::Tab) && !ctx.wants_keyboard_input())
+            || (mouse.just_pressed(MouseButton::Right) && !ctx.is_pointer_over_area());
+        if !open {
+            return;
+        }
+
+        let Some(cursor) = window_q.single().cursor_position() else {
+            return;
+        };
+        let (camera, camera_transform) = camera_q.single();
+        let Some(world_pos) = camera.viewport_to_world_2d(camera_transform, cursor) else {
+            return;
+        };
+        let grid_pos = grid_q
+            .single()
+            .affine()
+            .inverse()
+            .transform_point3(world_pos.extend(0.0))
+            .truncate();
+
+        ui_state.node_menu = Some(NodeMenuState {
+            pos: (cursor.x, cursor.y),
+            grid_pos,
+            search: String::new(),
+        });
+        just_opened = true;
+    } else if keys.just_pressed(KeyCode::Escape)
+        || (mouse.get_just_pressed().next().is_some() && !ctx.is_pointer_over_area())
+    {
+        ui_state.node_menu = None;
+        return;
+    }
+
+    let menu = ui_state.node_menu.as_mut().unwrap();
+    let labels = registry
+        .iter()
+        .map(|op| format!("{} ({})", op.display_name, op.category))
+        .collect::<Vec<_>>();
+    let categories = registry
+        .iter()
+        .map(|op| op.category)
+        .collect::<BTreeSet<_>>();
+
+    let mut chosen = None;
+    egui::Window::new("Create Op")
+        .fixed_pos(menu.pos)
+        .resizable(false)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            let search = ui.add(
+                AutoCompleteTextEdit::new(&mut menu.search, &labels)
+                    .max_suggestions(10)
+                    .highlight_matches(true),
+            );
+            if just_opened {
+                search.request_focus();
+            }
+            // Picking a suggestion fills in its full label
+            if let Some(idx) = labels.iter().position(|label| *label == menu.search) {
+                chosen = registry.iter().nth(idx);
+            }
+
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    for category in &categories {
+                        ui.collapsing(*category, |ui| {
+                            for op in registry.by_category(category) {
+                                if ui.button(op.display_name).clicked() {
+                                    chosen = Some(op);
+                                }
+                            }
+                        });
+                    }
+                });
+        });
+
+    let Some(op) = chosen else {
+        return;
+    };
+
+    let ty = op.name;
+    let position = menu.grid_pos;
+    let network = current_container
+        .0
+        .as_ref()
+        .map(|container| container.0.clone());
+    commands.add(move |world: &mut World| {
+        let index = world.resource::<UniqueIndex<OpName>>();
+        let name = OpName::unique(network.as_deref(), ty, |name| index.contains_key(name));
+        if let Some(mut entity) = spawn_op(world, ty, name.clone()) {
+            entity.insert((OpOwner::User, NodePosition(position)));
+            world.resource_mut::<History>().record(Edit::Create {
+                op: OpData {
+                    name: name.0,
+                    ty: ty.to_string(),
+                    position: Some(position),
+                    bypass: false,
+                    script: None,
+                    params: vec![],
+                },
+                connections: vec![],
+            });
+        }
+    });
+    ui_state.node_menu = None;
+}
@@ -0,0 +1,267 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::engine::animation::{Interpolation, Keyframe, Keyframes, Timeline};
+use crate::engine::history::{Edit, History};
+use crate::engine::op::OpName;
+use crate::engine::param::ParamName;
+use crate::ui::graph::SelectedNode;
+use crate::ui::UiState;
+use crate::Sets;
+
+/// The width of the param names left of the tracks.
+const LABEL_WIDTH: f32 = 100.0;
+const TRACK_HEIGHT: f32 = 18.0;
+const KEY_SIZE: f32 = 5.0;
+
+pub struct TimelinePlugin;
+
+impl Plugin for TimelinePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, timeline_ui.in_set(Sets::Ui));
+    }
+}
+
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+// Systems
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+fn timeline_ui(
+    mut commands: Commands,
+    mut egui_contexts: EguiContexts,
+    mut ui_state: ResMut<UiState>,
+    mut time: ResMut<Time<Virtual>>,
+    mut timeline: ResMut<Timeline>,
+    selected_q: Query<(&OpName, &Children), With<SelectedNode>>,
+    params_q: Query<(&ParamName, &Keyframes)>,
+    mut history: ResMut<History>,
+) {
+    let selected = selected_q.get_single().ok();
+
+    ui_state.timeline = Some(
+        egui::TopBottomPanel::bottom("timeline")
+            .resizable(false)
+            .show(egui_contexts.ctx_mut(), |ui| {
+                ui.horizontal(|ui| {
+                    let play = if time.is_paused() { "Play" } else { "Pause" };
+                    if ui.button(play).clicked() {
+                        if time.is_paused() {
+                            time.unpause();
+                        } else {
+                            time.pause();
+                        }
+                    }
+                    if ui.button("Rewind").clicked() {
+                        timeline.time = timeline.loop_start;
+                    }
+                    ui.label(format!("{:.2} s", timeline.time));
+                    ui.separator();
+
+                    let length = timeline.length;
+                    ui.checkbox(&mut timeline.looping, "Loop");
+                    ui.add(
+                        egui::DragValue::new(&mut timeline.loop_start)
+                            .clamp_range(0.0..=length)
+                            .speed(0.05)
+                            .suffix(" s"),
+                    );
+                    ui.add(
+                        egui::DragValue::new(&mut timeline.loop_end)
+                            .clamp_range(0.0..=length)
+                            .speed(0.05)
+                            .suffix(" s"),
+                    );
+                    ui.separator();
+                    ui.label("Length");
+                    ui.add(
+                        egui::DragValue::new(&mut timeline.length)
+                            .clamp_range(1.0..=3600.0)
+                            .speed(0.1)
+                            .suffix(" s"),
+                    );
+                });
+
+                ui.horizontal(|ui| {
+                    ui.add_sized([LABEL_WIDTH, TRACK_HEIGHT], egui::Label::new(""));
+                    scrub_bar(ui, &mut timeline);
+                });
+
+                let Some((op_name, children)) = selected else {
+                    return;
+                };
+                for param in children.iter() {
+                    let Ok((name, keyframes)) = params_q.get(*param) else {
+                        continue;
+                    };
+
+                    ui.horizontal(|ui| {
+                        ui.add_sized([LABEL_WIDTH, TRACK_HEIGHT], egui::Label::new(&name.0));
+                        let mut edited = keyframes.clone();
+                        track(ui, *param, &mut edited, &mut timeline);
+                        if edited == *keyframes {
+                            return;
+                        }
+
+                        let to = if edited.is_empty() {
+                            commands.entity(*param).remove::<Keyframes>();
+                            None
+                        } else {
+                            commands.entity(*param).insert(edited.clone());
+                            Some(edited)
+                        };
+                        history.record(Edit::Keyframes {
+                            op: op_name.clone(),
+                            param: name.0.clone(),
+                            from: Some(keyframes.clone()),
+                            to,
+                        });
+                    });
+                }
+            })
+            .response,
+    );
+}
+
+/// The time ruler, which moves the playhead when clicked or dragged.
+fn scrub_bar(ui: &mut egui::Ui, timeline: &mut Timeline) {
+    let width = ui.available_width();
+    let (rect, response) = ui.allocate_exact_size(
+        egui::vec2(width, TRACK_HEIGHT),
+        egui::Sense::click_and_drag(),
+    );
+    let length = timeline.length;
+    let to_x = move |time: f32| rect.left() + time / length * rect.width();
+
+    let painter = ui.painter_at(rect);
+    let visuals = ui.visuals();
+    painter.rect_filled(rect, 0.0, visuals.extreme_bg_color);
+    if timeline.looping {
+        let range = egui::Rect::from_x_y_ranges(
+            to_x(timeline.loop_start)..=to_x(timeline.loop_end),
+            rect.y_range(),
+        );
+        painter.rect_filled(range, 0.0, visuals.selection.bg_fill.gamma_multiply(0.4));
+    }
+    for second in 0..=timeline.length as u32 {
+        let x = to_x(second as f32);
+        painter.line_segment(
+            [
+                egui::pos2(x, rect.bottom() - 4.0),
+                egui::pos2(x, rect.bottom()),
+            ],
+            visuals.widgets.noninteractive.fg_stroke,
+        );
+        painter.text(
+            egui::pos2(x + 2.0, rect.top()),
+            egui::Align2::LEFT_TOP,
+            second.to_string(),
+            egui::FontId::monospace(9.0),
+            visuals.weak_text_color(),
+        );
+    }
+    playhead(&painter, rect, to_x(timeline.time));
+
+    if let Some(pos) = response.interact_pointer_pos() {
+        timeline.time = x_to_time(rect, timeline.length, pos.x);
+    }
+}
+
+/// The keyframes of a param, which can be dragged, deleted or changed from their context menu.
+/// Double-clicking the track adds a keyframe at the playhead.
+fn track(ui: &mut egui::Ui, param: Entity, keyframes: &mut Keyframes, timeline: &mut Timeline) {
+    let width = ui.available_width();
+    let (rect, response) =
+        ui.allocate_exact_size(egui::vec2(width, TRACK_HEIGHT), egui::Sense::click());
+    let length = timeline.length;
+    let to_x = move |time: f32| rect.left() + time / length * rect.width();
+
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, ui.visuals().faint_bg_color);
+    playhead(&painter, rect, to_x(timeline.time));
+
+    if response.double_clicked() {
+        if let Some(value) = keyframes.sample(timeline.time) {
+            keyframes.insert(Keyframe {
+                time: timeline.time,
+                value,
+                interpolation: Interpolation::default(),
+            });
+        }
+    } else if let Some(pos) = response.interact_pointer_pos() {
+        timeline.time = x_to_time(rect, timeline.length, pos.x);
+    }
+
+    let mut delete = None;
+    for idx in 0..keyframes.len() {
+        let center = egui::pos2(to_x(keyframes[idx].time), rect.center().y);
+        let key_rect = egui::Rect::from_center_size(center, egui::Vec2::splat(KEY_SIZE * 2.0));
+        let key_response = ui.interact(
+            key_rect,
+            ui.id().with((param, idx)),
+            egui::Sense::click_and_drag(),
+        );
+
+        // Keys can't be dragged past their neighbours, so they stay sorted
+        if key_response.dragged() {
+            if let Some(pos) = key_response.interact_pointer_pos() {
+                let min = idx
+                    .checked_sub(1)
+                    .map_or(0.0, |prev| keyframes[prev].time + 0.01);
+                let max = keyframes
+                    .get(idx + 1)
+                    .map_or(timeline.length, |next| next.time - 0.01);
+                keyframes[idx].time = x_to_time(rect, timeline.length, pos.x).clamp(min, max);
+            }
+        }
+        key_response.context_menu(|ui| {
+            for interpolation in [
+                Interpolation::Linear,
+                Interpolation::Step,
+                Interpolation::EASE,
+                Interpolation::Slerp,
+            ] {
+                let selected = keyframes[idx].interpolation.as_str() == interpolation.as_str();
+                if ui
+                    .selectable_label(selected, interpolation.as_str())
+                    .clicked()
+                {
+                    keyframes[idx].interpolation = interpolation;
+                    ui.close_menu();
+                }
+            }
+            ui.separator();
+            if ui.button("Delete").clicked() {
+                delete = Some(idx);
+                ui.close_menu();
+            }
+        });
+
+        let visuals = ui.style().interact(&key_response);
+        let points = vec![
+            center + egui::vec2(0.0, -KEY_SIZE),
+            center + egui::vec2(KEY_SIZE, 0.0),
+            center + egui::vec2(0.0, KEY_SIZE),
+            center + egui::vec2(-KEY_SIZE, 0.0),
+        ];
+        painter.add(egui::Shape::convex_polygon(
+            points,
+            visuals.fg_stroke.color,
+            visuals.bg_stroke,
+        ));
+    }
+
+    if let Some(idx) = delete {
+        keyframes.remove(idx);
+    }
+}
+
+fn playhead(painter: &egui::Painter, rect: egui::Rect, x: f32) {
+    painter.line_segment(
+        [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+        egui::Stroke::new(1.5, egui::Color32::RED),
+    );
+}
+
+fn x_to_time(rect: egui::Rect, length: f32, x: f32) -> f32 {
+    ((x - rect.left()) / rect.width() * length).clamp(0.0, length)
+}